@@ -0,0 +1,208 @@
+use regex_automata::{meta, util::syntax};
+
+use crate::{frequency::FrequencyTable, matchers::Boundary, types::Error, MultiRegex, Regex, RegexBuilder};
+
+/// How the lines (or records) of the searched text end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineTerminator {
+    /// Each line ends with the given ASCII byte, like a line feed, or a NUL byte.
+    Byte(u8),
+    /// Each line ends with a line feed, which may be preceded by a carriage return.
+    Crlf,
+}
+
+/// The options that influence how a pattern is compiled and matched.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// The byte frequencies used to choose the literal fragments of the patterns.
+    pub frequencies: FrequencyTable,
+    /// Whether the matches must be whole words.
+    pub whole_word: bool,
+    /// Whether the matches must be whole lines. Takes precedence over `whole_word`.
+    pub whole_line: bool,
+    /// Whether the patterns are plain strings instead of regular expressions.
+    pub fixed_strings: bool,
+    /// How the lines of the searched text end.
+    pub terminator: LineTerminator,
+    /// Whether `^` and `$` match at the start and end of every line, instead of
+    /// only at the start and end of the text.
+    pub multi_line: bool,
+    /// Whether the matchers count the candidates of their prefilters.
+    pub stats: bool,
+}
+
+impl Default for LineTerminator {
+    /// Lines end with a line feed by default.
+    fn default() -> LineTerminator {
+        return LineTerminator::Byte(b'\n');
+    }
+}
+
+impl LineTerminator {
+    /// Returns the byte that ends each line.
+    pub fn byte(&self) -> u8 {
+        return match self {
+            LineTerminator::Byte(byte) => *byte,
+            LineTerminator::Crlf => b'\n',
+        };
+    }
+
+    /// Returns the position of the last line terminator before the given position, if any.
+    pub fn rfind(&self, text: &str, pos: usize) -> Option<usize> {
+        return text.as_bytes()[..pos].iter().rposition(|b| *b == self.byte());
+    }
+
+    /// Returns the position of the first line terminator at or after the given position, if any.
+    pub fn find(&self, text: &str, pos: usize) -> Option<usize> {
+        return text.as_bytes()[pos..].iter().position(|b| *b == self.byte()).map(|i| pos + i);
+    }
+
+    /// Returns the position after the first line boundary at or after the given position, as
+    /// the anchors of the multi-line mode see it: in CRLF mode, a lone carriage return ends
+    /// a line too, which the anchors don't match after, if a line feed follows it.
+    pub fn next_line(&self, text: &str, pos: usize) -> Option<usize> {
+        return text.as_bytes()[pos..].iter().position(|b| self.ends_line(*b)).map(|i| pos + i + 1);
+    }
+
+    /// Determines whether the anchors of the multi-line mode may match after the given byte.
+    pub fn ends_line(&self, byte: u8) -> bool {
+        return byte == self.byte() || (*self == LineTerminator::Crlf && byte == b'\r');
+    }
+
+    /// Sets the line terminator in the given options of an automaton, which the anchors
+    /// of the multi-line mode match around (along with the syntax options).
+    pub fn configure(&self, config: meta::Config) -> meta::Config {
+        return config.line_terminator(self.byte());
+    }
+}
+
+impl Config {
+    /// Compiles the given pattern with the library-supplied matcher, so that '.' doesn't match
+    /// the line terminator, and the anchors of the multi-line mode match around it.
+    pub fn compile(&self, pattern: &str) -> Result<regex::Regex, regex::Error> {
+        return regex::RegexBuilder::new(pattern)
+            .line_terminator(self.terminator.byte())
+            .crlf(self.terminator == LineTerminator::Crlf)
+            .multi_line(self.multi_line)
+            .build();
+    }
+
+    /// Returns the syntax options of the automata compiled from the patterns, with the
+    /// line terminator and the multi-line mode.
+    pub fn syntax(&self) -> syntax::Config {
+        return syntax::Config::new()
+            .line_terminator(self.terminator.byte())
+            .crlf(self.terminator == LineTerminator::Crlf)
+            .multi_line(self.multi_line);
+    }
+
+    /// Returns the boundaries the matches must have, if any.
+    pub fn boundary(&self) -> Option<Boundary> {
+        if self.whole_line {
+            return Some(Boundary::Line);
+        }
+        if self.whole_word {
+            return Some(Boundary::Word);
+        }
+
+        return None;
+    }
+}
+
+impl RegexBuilder {
+    /// Creates a new builder with the default options.
+    pub fn new() -> RegexBuilder {
+        return RegexBuilder { config: Config::default() };
+    }
+
+    /// Sets the byte frequency table used to choose the rarest literal fragment of each
+    /// pattern. By default, a built-in table modelled after English text is used, but
+    /// a table created from a sample of the searched corpus may give better results.
+    pub fn frequencies(&mut self, frequencies: FrequencyTable) -> &mut RegexBuilder {
+        self.config.frequencies = frequencies;
+        return self;
+    }
+
+    /// Only reports matches that are whole words: neither the character before the
+    /// match nor the one after it may be a word character. The patterns are compiled
+    /// as usual, and the matches they find are checked for the boundaries afterwards,
+    /// so literal patterns keep their fast search.
+    pub fn whole_word(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.config.whole_word = yes;
+        return self;
+    }
+
+    /// Only reports matches that span whole lines, from the start of a line to its end.
+    /// Like `whole_word`, the boundaries are checked after a match is found. If both
+    /// options are set, the matches must be whole lines.
+    pub fn whole_line(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.config.whole_line = yes;
+        return self;
+    }
+
+    /// Treats the patterns as plain strings that only match themselves, instead of regular
+    /// expressions. The patterns are not validated or analyzed at all, they are matched
+    /// with the literal heuristic directly (and multiple ones with the Wu-Manber algorithm).
+    pub fn fixed_strings(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.config.fixed_strings = yes;
+        return self;
+    }
+
+    /// Sets the byte that ends each line (or record) of the searched text, like a NUL byte for
+    /// the output of `find -print0`. The default is a line feed. Matches of unknown length
+    /// are searched for within the line of their literal fragment, '.' doesn't match the
+    /// terminator, and whole line matches are bounded by it. Panics if the byte is not ASCII.
+    pub fn line_terminator(&mut self, byte: u8) -> &mut RegexBuilder {
+        assert!(byte.is_ascii(), "The line terminator must be an ASCII byte.");
+        self.config.terminator = LineTerminator::Byte(byte);
+        return self;
+    }
+
+    /// Ends each line with a line feed, which may be preceded by a carriage return: the end
+    /// of a whole line match is before the carriage return, and '.' doesn't match it. This
+    /// overrides the terminator set with `line_terminator`, and is overridden by it if it's
+    /// called later. Disabling it only restores the default terminator, if it was enabled.
+    pub fn crlf(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.config.terminator = match self.config.terminator {
+            _ if yes => LineTerminator::Crlf,
+            LineTerminator::Crlf => LineTerminator::default(),
+            terminator => terminator
+        };
+        return self;
+    }
+
+    /// Makes `^` and `$` match at the start and end of every line (as ended by the line
+    /// terminator), instead of only at the start and end of the text, as if every pattern
+    /// started with the `(?m)` flag. Unlike the flag, the option doesn't prevent the use
+    /// of the heuristics.
+    pub fn multi_line(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.config.multi_line = yes;
+        return self;
+    }
+
+    /// Counts the candidates found by the prefilters of the matchers, and the ones confirmed
+    /// by the patterns, as returned by `RegexMatcher::stats`. Counting is disabled by default,
+    /// as it updates shared counters during every search. Matchers loaded with `from_bytes`
+    /// don't count either, as the option is not serialized.
+    pub fn stats(&mut self, yes: bool) -> &mut RegexBuilder {
+        self.config.stats = yes;
+        return self;
+    }
+
+    /// Compiles the given pattern into a single pattern matcher with the set options.
+    pub fn build<'p>(&self, pattern: &'p str) -> Result<Regex<'p>, Error> {
+        return Regex::with_config(pattern, &self.config);
+    }
+
+    /// Compiles the given patterns into a multi-pattern matcher with the set options.
+    pub fn build_multi<'p>(&self, patterns: &'p [&'p str]) -> Result<MultiRegex<'p>, Error> {
+        return MultiRegex::with_config(patterns, &self.config);
+    }
+}
+
+impl Default for RegexBuilder {
+    /// Creates a new builder with the default options.
+    fn default() -> RegexBuilder {
+        return RegexBuilder::new();
+    }
+}
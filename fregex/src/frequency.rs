@@ -0,0 +1,77 @@
+/// A byte frequency table, used to estimate how rare a literal fragment is in the
+/// searched text. Rarer fragments produce fewer false candidates, and as such, are
+/// better suited for filtering the text before running the full pattern.
+#[derive(Clone, Debug)]
+pub struct FrequencyTable {
+    /// The (relative) number of occurrences of each byte.
+    counts: [u64; 256],
+    /// The sum of every count in the table.
+    total: u64,
+}
+
+/// Approximate relative frequencies of the lowercase English letters, per ten thousand letters.
+const LETTERS: [u64; 26] = [
+    817, 149, 278, 425, 1270, 223, 202, 609, 697, 15, 77, 403, 241,
+    675, 751, 193, 10, 599, 633, 906, 276, 98, 236, 15, 197, 7,
+];
+
+impl FrequencyTable {
+    /// Creates a table from the byte distribution of the given sample text.
+    /// Bytes that are absent from the sample are still assumed to be possible.
+    pub fn from_corpus(sample: &[u8]) -> FrequencyTable {
+        let mut counts = [1; 256];
+        for byte in sample {
+            counts[*byte as usize] += 1;
+        }
+
+        return FrequencyTable::from_counts(counts);
+    }
+
+    /// Creates a table from the given (relative) byte counts.
+    pub fn from_counts(counts: [u64; 256]) -> FrequencyTable {
+        // Zero counts would make a fragment infinitely rare, so every count is at least one.
+        let counts = counts.map(|c| c.max(1));
+        let total = counts.iter().sum();
+
+        return FrequencyTable { counts, total };
+    }
+
+    /// Returns the rarity score of the given fragment: the sum of the self-information
+    /// of each of its bytes (in bits). The higher the score, the fewer false candidates
+    /// the fragment is expected to produce. An empty fragment has a score of zero.
+    pub fn score(&self, fragment: &str) -> f64 {
+        return fragment
+            .bytes()
+            .map(|b| (self.total as f64 / self.counts[b as usize] as f64).log2())
+            .sum();
+    }
+}
+
+impl Default for FrequencyTable {
+    /// Creates the built-in table, modelled after English prose and source code.
+    fn default() -> FrequencyTable {
+        let mut counts = [1; 256];
+
+        // Letters, with uppercase ones being much less common than lowercase ones.
+        for (i, count) in LETTERS.iter().enumerate() {
+            counts[b'a' as usize + i] = *count;
+            counts[b'A' as usize + i] = count / 10 + 1;
+        }
+
+        // Digits, whitespace and common punctuation.
+        for digit in b'0'..=b'9' {
+            counts[digit as usize] = 50;
+        }
+        for (byte, count) in [
+            (b' ', 1800), (b'\n', 200), (b'\t', 20), (b'.', 100), (b',', 100), (b'-', 30),
+            (b'_', 30), (b'"', 25), (b'\'', 25), (b'(', 20), (b')', 20), (b':', 20), (b';', 15),
+            (b'/', 15), (b'=', 15), (b'!', 5), (b'?', 5), (b'[', 5), (b']', 5),
+            (b'{', 5), (b'}', 5), (b'<', 5), (b'>', 5), (b'*', 5), (b'+', 5), (b'&', 3),
+            (b'%', 2), (b'$', 2), (b'#', 2), (b'@', 2), (b'|', 2), (b'\\', 2), (b'~', 1),
+        ] {
+            counts[byte as usize] = count;
+        }
+
+        return FrequencyTable::from_counts(counts);
+    }
+}
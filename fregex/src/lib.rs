@@ -1,22 +1,60 @@
+use builder::Config;
 use matcher::Matcher;
-use types::Match;
+use multimatcher::PatternEntry;
+use types::{Explanation, Match, Stats, Strategy};
 
+pub(crate) mod builder;
+pub mod frequency;
 pub(crate) mod matcher;
 pub(crate) mod matchers;
 pub(crate) mod multimatcher;
 pub(crate) mod multimatchers;
 pub(crate) mod preprocessor;
+pub(crate) mod serialize;
 pub mod types;
 
+/// Restricts the implementations of the `RegexMatcher` trait to the matchers of this crate.
+mod sealed {
+    /// A trait that can't be named outside of the crate.
+    pub trait Sealed {}
+}
+
 /// A common trait shared by the `Regex` and `MultiRegex` structs. Can be used for dynamic
 /// dispatch between the two matchers.
-pub trait RegexMatcher {
+///
+/// The trait is sealed: it can't be implemented outside of this crate, so new methods
+/// can be added to it without breaking the users of the crate.
+pub trait RegexMatcher: sealed::Sealed {
     /// Determines whether the given text contains any matches for the compiled pattern(s).
     fn is_match<'t>(&self, text: &'t str) -> bool;
 
     /// Finds the first match of the compiled pattern present
     /// in the text, or returns None if no matches are found.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>>;
+
+    /// Finds the first match of the compiled pattern(s) present in the text, paired with the
+    /// id of the matching pattern, or returns None if no matches are found. The id of a single
+    /// pattern is always 0.
+    fn find_with_id<'t>(&self, text: &'t str) -> Option<(Match<'t>, usize)>;
+
+    /// Finds the first match of the compiled pattern(s) starting at or after the given byte
+    /// offset. Unlike searching in a slice of the text, the text before the offset is still
+    /// seen by the anchors and the word boundaries, so `^` doesn't match at the offset unless
+    /// it is the start of the text. The offsets of the match are relative to the whole text.
+    /// Panics if the offset is not at a character boundary of the text.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>>;
+
+    /// Finds the first match of the compiled pattern(s) starting at or after the given byte
+    /// offset, paired with the id of the matching pattern, like `find_at`.
+    fn find_with_id_at<'t>(&self, text: &'t str, start: usize) -> Option<(Match<'t>, usize)>;
+
+    /// Describes the matching strategy chosen for the compiled pattern(s): the heuristic
+    /// used for each pattern, the extracted literals and the bounds of the match lengths.
+    fn explain(&self) -> Explanation;
+
+    /// Returns the counters of the candidates found by the prefilters of the compiled
+    /// pattern(s), and of the ones confirmed by verifying them, across every search.
+    fn stats(&self) -> Stats;
 }
 
 /// A compiled regular expression pattern matcher. Can be used to find the first
@@ -28,6 +66,9 @@ pub trait RegexMatcher {
 pub struct Regex<'p> {
     /// A specific matcher implementation that will be used for searching.
     matcher: Box<dyn Matcher + 'p>,
+    /// The options used to compile the pattern. The line terminator and the multi-line
+    /// mode are stored along with the serialized matcher.
+    config: Config,
 }
 
 /// A compiled regular expression pattern matcher. Can be used to find the first
@@ -37,7 +78,27 @@ pub struct Regex<'p> {
 /// The underlying algorithm depends on the structures of the patterns, which are
 /// analyzed during the compilation phase. This implementation dynamically chooses
 /// the best performing algorithm for any given set of patterns.
+/// 
+/// Patterns can be added and removed after compilation. Each pattern keeps the id it
+/// was given (its index in the original slice, or the value returned when it was added),
+/// and the compiled matcher is only rebuilt if the matching strategy has to change.
 pub struct MultiRegex<'p> {
     /// A specific matcher implementation that will be used for searching.
     matcher: Box<dyn Matcher + 'p>,
+    /// The strategy of the compiled matcher.
+    strategy: Strategy,
+    /// Every pattern of the set, indexed by its id. Removed patterns are None.
+    patterns: Vec<Option<PatternEntry>>,
+    /// The id of the pattern at each index of the compiled matcher. Patterns
+    /// removed without rebuilding the matcher are None.
+    ids: Vec<Option<usize>>,
+    /// The options used to compile the patterns, which are reused for added patterns.
+    config: Config,
+}
+
+/// A builder that can be used to configure the compilation of `Regex` and `MultiRegex`
+/// matchers. Every matcher built with the same builder shares the same options.
+pub struct RegexBuilder {
+    /// The options passed to every compiled matcher.
+    config: Config,
 }
@@ -1,10 +1,15 @@
 use crate::{
-    matchers::{LiteralMatcher, LongestMatcher, NothingMatcher, PrefixMatcher},
-    preprocessor::{Preprocessor, Suggestion},
-    types::{Error, Match},
-    Regex, RegexMatcher
+    builder::Config,
+    matchers::{self, Boundary},
+    preprocessor::Preprocessor,
+    serialize::{self, Reader, Writer, KIND_SINGLE},
+    types::{Error, Explanation, Match, Stats, Suggestion},
+    sealed, Regex, RegexMatcher
 };
 
+/// An iterator over the overlapping matches of a matcher, paired with the index of the matching pattern.
+pub type Overlapping<'w, 't> = Box<dyn Iterator<Item = (Match<'t>, usize)> + 'w>;
+
 /// A trait to be implemented for each concrete matcher type.
 /// Can be used to find the compiled pattern in a given text.
 /// 
@@ -13,6 +18,80 @@ use crate::{
 pub trait Matcher {
     /// Find the compiled pattern in the given text.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>>;
+
+    /// Find the compiled pattern in the given text, starting the search at the given byte
+    /// offset. Unlike searching in a slice of the text, the characters before the offset
+    /// are still visible to anchors. Matchers of patterns without anchors can rely on the
+    /// default implementation, which searches in the slice after the offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return Some(self.find(&text[start..])?.offset(start));
+    }
+
+    /// Find the compiled patterns in the given text, starting from the given offset, and
+    /// return the match with the index of the matching pattern. Of matches with the same
+    /// start, the one of the pattern with the lower index is returned.
+    ///
+    /// Only the matches starting at or before the limit are needed by the caller, so the
+    /// search may be stopped there and None returned. A returned match is always the
+    /// earliest one, even if it starts after the limit.
+    fn find_indexed_at<'t>(&self, text: &'t str, start: usize, _limit: usize) -> Option<(Match<'t>, usize)> {
+        return Some((self.find_at(text, start)?, 0));
+    }
+
+    /// Find every overlapping match of the compiled patterns in the given text, paired with
+    /// the index of the matching pattern. Every position a pattern matches at is reported
+    /// once, with the match of the pattern starting there. The matches are ordered by their
+    /// start positions first, and by the index of their patterns second.
+    fn find_overlapping<'w, 't: 'w>(&'w self, text: &'t str) -> Overlapping<'w, 't> {
+        let mut pos = 0;
+
+        // Continue searching from the character after the start of each match.
+        return Box::new(std::iter::from_fn(move || {
+            if pos > text.len() {
+                return None;
+            }
+
+            let content = self.find_at(text, pos)?;
+            let skip = text[content.start()..].chars().next().map_or(1, |c| c.len_utf8());
+            pos = content.start() + skip;
+            return Some((content, 0));
+        }));
+    }
+
+    /// Determine which of the compiled patterns match anywhere in the given text.
+    /// Returns one flag for each pattern, in the order of the patterns.
+    fn matches(&self, text: &str) -> Vec<bool> {
+        return vec![self.find(text).is_some()];
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation;
+
+    /// Returns the counters of the candidates found by the prefilters of the matcher.
+    /// Matchers without a prefilter have nothing to count.
+    fn stats(&self) -> Stats {
+        return Stats::default();
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer);
+
+    /// Add a pattern to the compiled matcher without rebuilding it. Returns false if
+    /// the matcher can't be extended with the pattern, and has to be rebuilt instead.
+    fn insert(&mut self, _pattern: &str, _suggestion: Suggestion, _config: &Config) -> bool {
+        return false;
+    }
+
+    /// Remove the pattern with the given index from the compiled matcher without
+    /// rebuilding it. Returns false if the matcher has to be rebuilt instead.
+    fn remove(&mut self, _index: usize) -> bool {
+        return false;
+    }
+
+    /// Returns the boundaries the matches must have, if the matcher checks any.
+    fn boundary(&self) -> Option<Boundary> {
+        return None;
+    }
 }
 
 impl<'p> Regex<'p> {
@@ -20,8 +99,19 @@ impl<'p> Regex<'p> {
     /// 
     /// The function determines which internal matcher works best on the
     /// given pattern and instantiates it to be used during matching.
-    pub fn new(pattern: &'p str) -> Result<Regex, Error> {
-        let parse_result = Preprocessor::new(pattern).determine_type();
+    pub fn new(pattern: &'p str) -> Result<Regex<'p>, Error> {
+        return Regex::with_config(pattern, &Config::default());
+    }
+
+    /// Create a new regular expression matcher from the given pattern and options.
+    pub(crate) fn with_config(pattern: &'p str, config: &Config) -> Result<Regex<'p>, Error> {
+        // Fixed strings are always literal, only their special characters have to be escaped.
+        if config.fixed_strings {
+            let matcher = matchers::create(&regex::escape(pattern), Suggestion::Literal, config);
+            return Ok(Regex { matcher: matchers::bound(matcher, config), config: config.clone() });
+        }
+
+        let parse_result = Preprocessor::new(pattern, config).determine_type();
 
         // If the preprocessing failed, return with an error.
         if let Err(reason) = parse_result {
@@ -29,17 +119,32 @@ impl<'p> Regex<'p> {
         }
 
         // Else instantiate the correct matcher, and return with it.
-        let matcher: Box<dyn Matcher> = match parse_result.unwrap() {
-            Suggestion::Literal => Box::new(LiteralMatcher::new(pattern)),
-            Suggestion::Longest => Box::new(LongestMatcher::new(pattern)),
-            Suggestion::Prefix => Box::new(PrefixMatcher::new(pattern)),
-            Suggestion::Nothing => Box::new(NothingMatcher::new(pattern))
-        };
+        let matcher = matchers::bound(matchers::create(pattern, parse_result.unwrap(), config), config);
+
+        return Ok(Regex { matcher, config: config.clone() });
+    }
 
-        return Ok(Regex { matcher });
+    /// Serializes the compiled matcher into a versioned binary format, that can be
+    /// loaded with `Regex::from_bytes` without repeating the preprocessing.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(KIND_SINGLE, &self.config);
+        self.matcher.serialize(&mut writer);
+        return writer.into_bytes();
+    }
+
+    /// Loads a matcher serialized with `Regex::to_bytes`. The stored pattern is
+    /// validated again, and an error is returned if the data is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Regex<'static>, Error> {
+        let mut reader = Reader::new(bytes, KIND_SINGLE)?;
+        let matcher = serialize::read_matcher(&mut reader, serialize::REGEX_TAGS)?;
+        reader.finish()?;
+
+        return Ok(Regex { matcher, config: reader.config().clone() });
     }
 }
 
+impl sealed::Sealed for Regex<'_> {}
+
 impl RegexMatcher for Regex<'_> {
     /// Determines whether the given text contains any matches for the compiled pattern.
     fn is_match<'t>(&self, text: &'t str) -> bool {
@@ -51,4 +156,30 @@ impl RegexMatcher for Regex<'_> {
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
         return self.matcher.find(text);
     }
+
+    /// Finds the first match of the compiled pattern present in the text, paired with the id 0.
+    fn find_with_id<'t>(&self, text: &'t str) -> Option<(Match<'t>, usize)> {
+        return self.matcher.find(text).map(|m| (m, 0));
+    }
+
+    /// Finds the first match of the compiled pattern starting at or after the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return self.matcher.find_at(text, start);
+    }
+
+    /// Finds the first match of the compiled pattern starting at or after the given offset,
+    /// paired with the id 0.
+    fn find_with_id_at<'t>(&self, text: &'t str, start: usize) -> Option<(Match<'t>, usize)> {
+        return self.matcher.find_at(text, start).map(|m| (m, 0));
+    }
+
+    /// Describes the matching strategy chosen for the compiled pattern.
+    fn explain(&self) -> Explanation {
+        return self.matcher.explain();
+    }
+
+    /// Returns the counters of the candidates found by the prefilter of the compiled pattern.
+    fn stats(&self) -> Stats {
+        return self.matcher.stats();
+    }
 }
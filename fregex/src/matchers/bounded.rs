@@ -0,0 +1,172 @@
+use regex_automata::{meta, nfa::thompson::WhichCaptures, Anchored, Input, PatternID};
+
+use crate::{
+    builder::Config,
+    matcher::{Matcher, Overlapping},
+    serialize::{self, Reader, Writer, TAG_BOUNDED},
+    types::{Error, Explanation, Match, Stats}
+};
+
+use super::{BoundedMatcher, Boundary};
+
+impl Boundary {
+    /// Surrounds the given pattern with the assertions of the boundary.
+    fn wrap(&self, pattern: &str) -> String {
+        return match self {
+            Boundary::Word => format!(r"\b{{start-half}}(?:{})\b{{end-half}}", pattern),
+            Boundary::Line => format!("(?m:^)(?:{})(?m:$)", pattern),
+        };
+    }
+}
+
+impl BoundedMatcher {
+    /// Wraps the given matcher, so that it only reports matches with the given boundaries.
+    /// The lines of whole line matches end with the line terminator of the options.
+    pub fn new(inner: Box<dyn Matcher>, boundary: Boundary, config: &Config) -> BoundedMatcher {
+        let patterns: Vec<String> = inner
+            .explain()
+            .patterns
+            .iter()
+            .map(|p| boundary.wrap(&p.pattern))
+            .collect();
+
+        // The patterns were already validated, so only the size limits could make the
+        // compilation fail. The matches are only needed as a whole, so no capture groups
+        // are tracked.
+        let verifier = meta::Regex::builder()
+            .configure(config.terminator.configure(meta::Regex::config().which_captures(WhichCaptures::Implicit).nfa_size_limit(None)))
+            .syntax(config.syntax())
+            .build_many(&patterns)
+            .unwrap();
+
+        return BoundedMatcher { inner, verifier, boundary, terminator: config.terminator };
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag. The wrapped
+    /// matcher may be any of the given implementations, except for another wrapper.
+    pub fn deserialize(reader: &mut Reader, tags: &[u8]) -> Result<BoundedMatcher, Error> {
+        let boundary = match reader.read_u8()? {
+            0 => Boundary::Word,
+            1 => Boundary::Line,
+            _ => return Err(Error::Format("Unknown boundary type."))
+        };
+        let tags: Vec<u8> = tags.iter().copied().filter(|tag| *tag != TAG_BOUNDED).collect();
+        let inner = serialize::read_matcher(reader, &tags)?;
+
+        return Ok(BoundedMatcher::new(inner, boundary, reader.config()));
+    }
+
+    /// Finds the match with the boundaries that starts at the given position. Of the patterns
+    /// matching there, the one with the lowest index is chosen, unless a pattern is given.
+    fn verify<'t>(&self, text: &'t str, start: usize, pattern: Option<usize>) -> Option<(Match<'t>, usize)> {
+        let anchored = match pattern {
+            Some(index) => Anchored::Pattern(PatternID::must(index)),
+            None => Anchored::Yes
+        };
+
+        // The text before the candidate is kept, so that the boundaries are evaluated in context.
+        let result = self.verifier.search(&Input::new(text).range(start..).anchored(anchored))?;
+        let matched = Match::new(result.start(), result.end(), &text[result.range()]);
+
+        return Some((matched, result.pattern().as_usize()));
+    }
+
+    /// Determines whether a match with the boundaries may start at the given position.
+    fn may_start(&self, text: &str, pos: usize) -> bool {
+        return self.boundary != Boundary::Line || pos == 0 || self.terminator.ends_line(text.as_bytes()[pos - 1]);
+    }
+
+    /// Returns the position after the given candidate, where the next one should be searched from.
+    /// Returns None if there are no more positions a match with the boundaries could start at.
+    fn next_position(&self, text: &str, pos: usize) -> Option<usize> {
+        return match self.boundary {
+            // Matches can start at any character.
+            Boundary::Word => text[pos..].chars().next().map(|c| pos + c.len_utf8()),
+            // Matches can only start at the start of the next line, as the anchors see it.
+            Boundary::Line => self.terminator.next_line(text, pos),
+        };
+    }
+}
+
+impl Matcher for BoundedMatcher {
+    /// Find the compiled patterns with the boundaries in the given text.
+    fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, 0, text.len()).map(|(m, _)| m);
+    }
+
+    /// Find the compiled patterns with the boundaries in the given text, starting at the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, start, text.len()).map(|(m, _)| m);
+    }
+
+    /// Find the compiled patterns with the boundaries in the given text, starting at the given
+    /// offset, and return the index of the matching pattern too. The wrapped matcher finds the
+    /// earliest candidate, and if it has no match with the boundaries, the search continues
+    /// after it.
+    fn find_indexed_at<'t>(&self, text: &'t str, start: usize, limit: usize) -> Option<(Match<'t>, usize)> {
+        let mut pos = start;
+
+        loop {
+            let (candidate, _) = self.inner.find_indexed_at(text, pos, limit)?;
+
+            // No match can start before the candidate, but a pattern may have a match with
+            // the boundaries there that is different from the one found.
+            if self.may_start(text, candidate.start()) {
+                if let Some(found) = self.verify(text, candidate.start(), None) {
+                    return Some(found);
+                }
+            }
+
+            pos = self.next_position(text, candidate.start())?;
+            if pos > limit {
+                return None;
+            }
+        }
+    }
+
+    /// Find every overlapping match of the compiled patterns with the boundaries. Every
+    /// match of the wrapped matcher is verified with the automaton of its own pattern.
+    fn find_overlapping<'w, 't: 'w>(&'w self, text: &'t str) -> Overlapping<'w, 't> {
+        return Box::new(self.inner
+            .find_overlapping(text)
+            .filter(move |(m, _)| self.may_start(text, m.start()))
+            .filter_map(move |(m, index)| self.verify(text, m.start(), Some(index))));
+    }
+
+    /// Determine which of the compiled patterns have a match with the boundaries.
+    fn matches(&self, text: &str) -> Vec<bool> {
+        let mut matched = vec![false; self.verifier.pattern_len()];
+
+        for (_, index) in self.find_overlapping(text) {
+            matched[index] = true;
+        }
+
+        return matched;
+    }
+
+    /// Describe the strategy used by the wrapped matcher.
+    fn explain(&self) -> Explanation {
+        return self.inner.explain();
+    }
+
+    /// Returns the counters of the wrapped matcher, which finds the candidates.
+    fn stats(&self) -> Stats {
+        return self.inner.stats();
+    }
+
+    /// Write the tag, the boundary type and the wrapped matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_BOUNDED);
+        writer.write_u8(match self.boundary {
+            Boundary::Word => 0,
+            Boundary::Line => 1,
+        });
+
+        self.inner.serialize(writer);
+    }
+
+    /// Return the boundaries the matches must have.
+    fn boundary(&self) -> Option<Boundary> {
+        return Some(self.boundary);
+    }
+}
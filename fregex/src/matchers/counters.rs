@@ -0,0 +1,42 @@
+use std::sync::atomic::Ordering;
+
+use crate::types::Stats;
+
+use super::Counters;
+
+impl Counters {
+    /// Creates new counters starting from zero, which only count if they are enabled.
+    pub fn new(enabled: bool) -> Counters {
+        return Counters { enabled, ..Counters::default() };
+    }
+
+    /// Counts a candidate, and whether it was confirmed.
+    pub fn record(&self, confirmed: bool) {
+        if !self.enabled {
+            return;
+        }
+
+        self.candidates.fetch_add(1, Ordering::Relaxed);
+        if confirmed {
+            self.confirmed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the counters as the ones of a literal prefilter.
+    pub fn stats(&self) -> Stats {
+        return Stats {
+            candidates: self.candidates.load(Ordering::Relaxed),
+            confirmed: self.confirmed.load(Ordering::Relaxed),
+            ..Stats::default()
+        };
+    }
+
+    /// Returns the counters as the ones of the Wu-Manber algorithm.
+    pub fn wu_manber_stats(&self) -> Stats {
+        return Stats {
+            wu_manber_candidates: self.candidates.load(Ordering::Relaxed),
+            wu_manber_confirmed: self.confirmed.load(Ordering::Relaxed),
+            ..Stats::default()
+        };
+    }
+}
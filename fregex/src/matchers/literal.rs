@@ -1,25 +1,66 @@
-use crate::{types::Match, matcher::Matcher};
+use crate::{
+    builder::Config,
+    matcher::Matcher,
+    preprocessor::Preprocessor,
+    serialize::{Reader, Writer, TAG_LITERAL},
+    types::{Error, Explanation, Match, PatternExplanation, Stats, Strategy, Suggestion}
+};
 
-use super::LiteralMatcher;
+use super::{Counters, LiteralMatcher};
 
-impl<'p> LiteralMatcher<'p> {
+impl LiteralMatcher {
     /// Create a new matcher with the supplied pattern.
-    pub fn new(pattern: &str) -> LiteralMatcher {
-        return LiteralMatcher { pattern };
+    pub fn new(pattern: &str, config: &Config) -> LiteralMatcher {
+        let literal = Preprocessor::new(pattern, config).literal();
+        return LiteralMatcher { pattern: pattern.to_string(), literal, counters: Counters::new(config.stats) };
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<LiteralMatcher, Error> {
+        let pattern = reader.read_str()?;
+        let literal = reader.read_str()?;
+        return Ok(LiteralMatcher { pattern, literal, counters: Counters::new(reader.config().stats) });
     }
 }
 
-impl<'p> Matcher for LiteralMatcher<'p> {
+impl Matcher for LiteralMatcher {
     /// Find the compiled pattern in the given text.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
-        let result = text.find(self.pattern);
+        let result = text.find(&self.literal);
 
         if let Some(start) = result {
-            let end = start + self.pattern.len();
+            // Every occurrence of the string is a candidate confirmed as a match.
+            self.counters.record(true);
+            let end = start + self.literal.len();
             let matched_text = &text[start..end];
             return Some(Match::new(start, end, matched_text));
         } else {
             return None;
         }
     }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let pattern = PatternExplanation {
+            pattern: self.pattern.clone(),
+            suggestion: Suggestion::Literal,
+            literals: vec![self.literal.clone()],
+            min_length: self.literal.len(),
+            max_length: Some(self.literal.len()),
+        };
+
+        return Explanation { strategy: Strategy::Single(Suggestion::Literal), patterns: vec![pattern], wu_manber: None };
+    }
+
+    /// Returns the counters of the occurrences of the string, each of them a confirmed candidate.
+    fn stats(&self) -> Stats {
+        return self.counters.stats();
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_LITERAL);
+        writer.write_str(&self.pattern);
+        writer.write_str(&self.literal);
+    }
 }
@@ -1,118 +1,68 @@
 use std::cmp::min;
 
-use regex::Regex;
-
-use crate::{types::Match, matcher::Matcher};
-
-use super::LongestMatcher;
+use crate::{
+    builder::Config,
+    matcher::Matcher,
+    preprocessor::Preprocessor,
+    serialize::{self, Reader, Writer, TAG_LONGEST},
+    types::{Error, Explanation, Match, PatternExplanation, Stats, Strategy, Suggestion}
+};
+
+use super::{ceil_boundary, floor_boundary, min_length, Counters, LongestMatcher};
+
+/// Returns the heuristic the preprocessor chooses for a pattern the longest heuristic is used with.
+fn suggestion(preprocessor: &Preprocessor) -> Suggestion {
+    return if preprocessor.is_literal() { Suggestion::Literal } else { Suggestion::Longest };
+}
 
 impl<'t> LongestMatcher {
     /// Create a new matcher with the supplied pattern.
-    pub fn new(pattern: &str) -> LongestMatcher {
+    pub fn new(pattern: &str, config: &Config) -> LongestMatcher {
         // Create library-supplied matcher.
-        let original = Regex::new(pattern).unwrap();
-
-        // Create properties of the final matcher.
-        let mut best_fragment = String::from("");
-        let mut max_length = 0;
-        let mut length_known = true;
-
-        // Create state variables.
-        let mut current = String::from("");
-        let mut state_escaped = false;
-        let mut iter = pattern.chars();
-
-        // While there's a character to parse from the pattern.
-        while let Some(c) = iter.next() {
-            // If something was escaped, push to the current fragment and continue.
-            if state_escaped {
-                current.push(c);
-                max_length += 1;
-                state_escaped = false;
-                continue;
-            }
+        let original = config.compile(pattern).unwrap();
 
-            // On a '\', set the escaped flag and continue.
-            if c == '\\' {
-                state_escaped = true;
-                continue;
-            }
+        // Choose the rarest literal fragment of the pattern, and determine the
+        // maximum length of a match, if possible.
+        let preprocessor = Preprocessor::new(pattern, config);
+        let best_fragment = preprocessor.best_fragment();
+        let max_length = preprocessor.max_length();
 
-            // Unless a char is a recognized special char, push to the current fragment
-            // and continue.
-            if !['.', '[', '+', '?', '*'].contains(&c) {
-                current.push(c);
-                max_length += 1;
-                continue;
-            }
-
-            // The remainder of this loop handles the special characters mentioned above.
-
-            // If the modifier makes the previous character optional, pop it from the fragment.
-            if c == '*' || c == '?' {
-                current.pop();
-            }
-
-            // With every special character, the current fragment ends. Save it if it is
-            // longer than our current best fragment.
-            if current.len() > best_fragment.len() {
-                best_fragment = current;
-                current = String::from("");
-            }
-
-            // If the char was '.', we also increase the max length.
-            if c == '.' {
-                max_length += 1;
-                continue;
-            }
+        // Return a matcher with the properties created above.
+        return LongestMatcher {
+            original,
+            best_fragment,
+            max_length: max_length.unwrap_or(0),
+            length_known: max_length.is_some(),
+            suggestion: suggestion(&preprocessor),
+            terminator: config.terminator,
+            counters: Counters::new(config.stats)
+        };
+    }
 
-            // If it was a '+' or a '*', we now can't know the length of a match.
-            if c == '+' || c == '*' {
-                length_known = false;
-                continue;
-            }
+    /// Read a matcher written by `serialize_state`.
+    pub fn deserialize(reader: &mut Reader) -> Result<LongestMatcher, Error> {
+        let original = serialize::compile(&reader.read_str()?, reader.config())?;
+        let best_fragment = reader.read_str()?;
+        let max_length = reader.read_usize()?;
+        let length_known = reader.read_bool()?;
 
-            // If it was a '?', there's nothing to do.
-            if c == '?' {
-                continue;
-            }
-
-            // If it was a '[', we parse the text up until the closing bracket.
-            // The characters inside are all different options, and as such, we
-            // cannot create a fragment from them.
-            if c == '[' {
-                let mut inside_escaped = false;
-                while let Some(c) = iter.next() {
-                    // An escaped character can't be the closing bracket.
-                    if inside_escaped {
-                        inside_escaped = false;
-                        continue;
-                    }
-
-                    // Manage escaped state.
-                    if c == '\\' {
-                        inside_escaped = true;
-                    }
-
-                    // Break on a closing bracket.
-                    if c == ']' {
-                        break;
-                    }
-                }
-
-                max_length += 1;
-                continue;
-            }
+        // The search window is broadened around the fragment based on the maximum length.
+        if length_known && max_length < best_fragment.len() {
+            return Err(Error::Format("Maximum length is shorter than the fragment."));
         }
 
-        // If we processed the whole string and there's still an unfinished fragment
-        // stored in the current string, check that too.
-        if current.len() > best_fragment.len() {
-            best_fragment = current;
-        }
+        // The heuristic isn't stored, since it is cheap to decide again.
+        let suggestion = suggestion(&Preprocessor::new(original.as_str(), reader.config()));
+        let terminator = reader.terminator();
+        return Ok(LongestMatcher { original, best_fragment, max_length, length_known, suggestion, terminator, counters: Counters::new(reader.config().stats) });
+    }
 
-        // Return a matcher with the properties created above.
-        return LongestMatcher { original, best_fragment, max_length, length_known };
+    /// Write the precomputed state of the matcher, without its tag.
+    pub fn serialize_state(&self, writer: &mut Writer) {
+        writer.write_str(self.original.as_str());
+        writer.write_str(&self.best_fragment);
+        writer.write_usize(self.max_length);
+        writer.write_bool(self.length_known);
     }
 
     /// Returns the best fragment stored in the matcher.
@@ -120,8 +70,14 @@ impl<'t> LongestMatcher {
         return &self.best_fragment;
     }
 
+    /// Returns the maximum length of a match, or None if it can't be decided.
+    pub fn max_length(&self) -> Option<usize> {
+        return if self.length_known { Some(self.max_length) } else { None };
+    }
+
     /// Given a text and a potential match candidate, try to locate the full match.
-    /// The second return value signifies the position before which no more matches can occur.
+    /// The second return value signifies the position from which the next candidate
+    /// should be searched for, as no more matches can contain an earlier one.
     pub fn locate_near(&self, text: &'t str, pos: usize) -> (Option<Match<'t>>, usize) {
         // Set the start and end coordinates.
         let mut start = pos;
@@ -132,20 +88,27 @@ impl<'t> LongestMatcher {
             // with the following delta.
             let delta = self.max_length - (end - start);
 
-            start = if start < delta { 0 } else { start - delta };
-            end = min(text.len(), end + delta);
+            // The range must not split the characters at its ends.
+            start = floor_boundary(text, start.saturating_sub(delta));
+            end = ceil_boundary(text, min(text.len(), end + delta));
         } else {
             // If we don't know the length, broaden the matching range to the
             // entire line the candidate was found in.
-            start = text[..start].rfind('\n').unwrap_or(0);
-            end = match text[end..].find('\n') {
-                Some(pos) => end + pos,
-                None => text.len()
-            };
+            start = self.terminator.rfind(text, start).unwrap_or(0);
+            end = self.terminator.find(text, end).unwrap_or(text.len());
         }
 
         // Now we try using the original matcher on this excerpt of the text.
         let result = self.original.find(&text[start..end]);
+        self.counters.record(result.is_some());
+
+        // Matches of known length containing a later candidate may extend past the
+        // searched range, but matches of unknown length are confined to the line.
+        let next = if self.length_known {
+            pos + self.best_fragment.chars().next().map_or(1, |c| c.len_utf8())
+        } else {
+            end
+        };
 
         // If we found something, return with the match, else return with none.
         if result.is_some() {
@@ -153,9 +116,9 @@ impl<'t> LongestMatcher {
             let match_start = content.start() + start;
             let match_end = content.end() + start;
             let matched_text = &text[match_start..match_end];
-            return (Some(Match::new(match_start, match_end, matched_text)), end);
+            return (Some(Match::new(match_start, match_end, matched_text)), next);
         } else {
-            return (None, end);
+            return (None, next);
         }
     }
 }
@@ -163,38 +126,57 @@ impl<'t> LongestMatcher {
 impl Matcher for LongestMatcher {
     /// Find the compiled pattern in the given text.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
-        // Create a mutable text slice and set global offset.
-        let mut text = text;
-        let mut global_offset: usize = 0;
-
-        // This loop searches for match candidates. If a candidate is found,
-        // but it is not a proper match, the start of the slice will be adjusted
-        // to after the candidate's end. We iterate until there's no more text to match.
-        while text.len() > 0 {
+        // The position from which the next candidate is searched for.
+        let mut offset: usize = 0;
+
+        // This loop searches for match candidates. If a candidate is found, but it is not
+        // a proper match, the search continues after it. The whole text is always passed to
+        // `locate_near`, as the range of a later candidate may start before this position.
+        while offset < text.len() {
             // Search for the best fragment. If it's not present, no match can be found.
-            let result = text.find(&self.best_fragment);
+            let result = text[offset..].find(&self.best_fragment);
             if result.is_none() {
                 return None;
             }
 
             // Locate the possible match.
-            let (result, end) = self.locate_near(text, result.unwrap());
+            let (result, next) = self.locate_near(text, offset + result.unwrap());
 
             // If we found something, return with the match.
             if result.is_some() {
-                let content = result.unwrap();
-                let match_start = content.start() + global_offset;
-                let match_end = content.end() + global_offset;
-                let matched_text = content.as_str();
-                return Some(Match::new(match_start, match_end, matched_text));
+                return result;
             }
 
             // Else adjust search range, and continue with the next iteration.
-            text = &text[end..];
-            global_offset += end;
+            offset = next;
         }
 
         // Return none if we ran out of text to search.
         return None;
     }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        // Every match contains the best fragment, so its length is a lower bound.
+        let pattern = PatternExplanation {
+            pattern: self.original.as_str().to_string(),
+            suggestion: self.suggestion,
+            literals: vec![self.best_fragment.clone()],
+            min_length: min_length(self.original.as_str()),
+            max_length: if self.length_known { Some(self.max_length) } else { None },
+        };
+
+        return Explanation { strategy: Strategy::Single(Suggestion::Longest), patterns: vec![pattern], wu_manber: None };
+    }
+
+    /// Returns the counters of the fragment candidates, and of the ones confirmed by the pattern.
+    fn stats(&self) -> Stats {
+        return self.counters.stats();
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_LONGEST);
+        self.serialize_state(writer);
+    }
 }
@@ -1,28 +1,60 @@
+use std::sync::atomic::AtomicU64;
+
 use regex::Regex;
+use regex_automata::{meta, util::syntax};
+
+use crate::{builder::{Config, LineTerminator}, matcher::Matcher, types::Suggestion};
 
+pub mod bounded;
+pub mod counters;
 pub mod literal;
 pub mod longest;
 pub mod nothing;
 pub mod prefix;
+pub mod suffix;
+
+/// Counters of the candidates found by a prefilter, and of the ones confirmed by the
+/// verification. They are atomic, so that the matchers can still be shared between threads.
+/// Nothing is counted unless they are enabled, to keep the atomic updates out of the search.
+#[derive(Debug, Default)]
+pub struct Counters {
+    /// Whether the candidates are counted.
+    enabled: bool,
+    /// The number of candidates found.
+    candidates: AtomicU64,
+    /// The number of candidates confirmed.
+    confirmed: AtomicU64,
+}
 
 /// A literal pattern matcher implementation. Technically
 /// nothing more than a wrapper for the `String::find()` method.
-pub struct LiteralMatcher<'p> {
-    /// The original pattern, stored as a string slice.
-    pattern: &'p str,
+pub struct LiteralMatcher {
+    /// The original pattern.
+    pattern: String,
+    /// The string matched by the pattern, with every escape sequence resolved.
+    literal: String,
+    /// The counters of the occurrences of the string, which are matches on their own.
+    counters: Counters,
 }
 
 /// A pattern matcher using the longest heuristic type.
 pub struct LongestMatcher {
     /// A library-supplied regex compilation struct with the original pattern.
     original: Regex,
-    /// The fragment used for pattern searching: the longest sequential character
-    /// string without a special character in it.
+    /// The fragment used for pattern searching: the rarest sequential character
+    /// string without a special character in it, according to the frequency table.
     best_fragment: String,
     /// The maximum length a match can be. Not used when `length_known` is false.
     max_length: usize,
     /// Whether the maximum length of a match can be decided or not.
     length_known: bool,
+    /// The heuristic chosen for the pattern by the preprocessor: literal patterns are
+    /// matched with this heuristic too, when they are mixed with the other patterns.
+    suggestion: Suggestion,
+    /// How the lines of the text end. Matches of unknown length are confined to a line.
+    terminator: LineTerminator,
+    /// The counters of the fragment candidates.
+    counters: Counters,
 }
 
 /// A pattern matcher using the prefix heuristic type.
@@ -31,6 +63,20 @@ pub struct PrefixMatcher {
     original: Regex,
     /// The prefix fragment to use for pattern searching.
     prefix: String,
+    /// The counters of the prefix candidates.
+    counters: Counters,
+}
+
+/// A pattern matcher using the suffix heuristic type.
+pub struct SuffixMatcher {
+    /// A library-supplied regex compilation struct with the original pattern.
+    original: Regex,
+    /// The suffix fragment to use for pattern searching.
+    suffix: String,
+    /// How the lines of the text end. Every match is confined to a line.
+    terminator: LineTerminator,
+    /// The counters of the suffix candidates.
+    counters: Counters,
 }
 
 /// A pattern matcher where no heuristics can be used. Simply delegates the tasks
@@ -39,3 +85,70 @@ pub struct NothingMatcher {
     /// A library-supplied regex compilation struct with the original pattern.
     original: Regex,
 }
+
+/// The boundaries a match must have to be reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    /// The characters around the match must not be word characters.
+    Word,
+    /// The match must start at the start of a line, and end at the end of one.
+    Line,
+}
+
+/// A wrapper around any matcher, that only reports the matches with the given boundaries.
+/// The wrapped matcher finds the candidates, which are verified by an automaton of the
+/// patterns surrounded by the boundary assertions.
+pub struct BoundedMatcher {
+    /// The matcher of the patterns, used to find the candidates.
+    inner: Box<dyn Matcher>,
+    /// The patterns with the boundary assertions, compiled into a single automaton.
+    verifier: meta::Regex,
+    /// The boundaries the matches must have.
+    boundary: Boundary,
+    /// How the lines of the text end, which bound the whole line matches.
+    terminator: LineTerminator,
+}
+
+/// Moves the given position back to the start of the character it is in.
+pub fn floor_boundary(text: &str, mut pos: usize) -> usize {
+    while !text.is_char_boundary(pos) {
+        pos -= 1;
+    }
+
+    return pos;
+}
+
+/// Moves the given position forward to the end of the character it is in.
+pub fn ceil_boundary(text: &str, mut pos: usize) -> usize {
+    while !text.is_char_boundary(pos) {
+        pos += 1;
+    }
+
+    return pos;
+}
+
+/// Returns the minimum length (in bytes) a match of the given pattern can have,
+/// or 0, if the pattern can never match.
+pub fn min_length(pattern: &str) -> usize {
+    return syntax::parse(pattern).ok().and_then(|hir| hir.properties().minimum_len()).unwrap_or(0);
+}
+
+/// Creates the single pattern matcher implementing the given heuristic type.
+pub fn create(pattern: &str, suggestion: Suggestion, config: &Config) -> Box<dyn Matcher> {
+    return match suggestion {
+        Suggestion::Literal => Box::new(LiteralMatcher::new(pattern, config)),
+        Suggestion::Longest => Box::new(LongestMatcher::new(pattern, config)),
+        Suggestion::Prefix => Box::new(PrefixMatcher::new(pattern, config)),
+        Suggestion::Suffix => Box::new(SuffixMatcher::new(pattern, config)),
+        Suggestion::Nothing => Box::new(NothingMatcher::new(pattern, config))
+    };
+}
+
+/// Wraps the given matcher, so that it only reports matches with the boundaries
+/// required by the options. Returns the matcher itself if no boundaries are required.
+pub fn bound(matcher: Box<dyn Matcher>, config: &Config) -> Box<dyn Matcher> {
+    return match config.boundary() {
+        Some(boundary) => Box::new(BoundedMatcher::new(matcher, boundary, config)),
+        None => matcher
+    };
+}
@@ -1,16 +1,25 @@
-use regex::Regex;
+use crate::{
+    builder::Config,
+    matcher::Matcher,
+    serialize::{self, Reader, Writer, TAG_NOTHING},
+    types::{Error, Explanation, Match, PatternExplanation, Strategy, Suggestion}
+};
 
-use crate::{matcher::Matcher, types::Match};
-
-use super::NothingMatcher;
+use super::{min_length, NothingMatcher};
 
 impl NothingMatcher {
     /// Create a new matcher with the supplied pattern.
-    pub fn new(pattern: &str) -> NothingMatcher {
+    pub fn new(pattern: &str, config: &Config) -> NothingMatcher {
         return NothingMatcher {
-            original: Regex::new(pattern).unwrap()
+            original: config.compile(pattern).unwrap()
         }
     }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<NothingMatcher, Error> {
+        let original = serialize::compile(&reader.read_str()?, reader.config())?;
+        return Ok(NothingMatcher { original });
+    }
 }
 
 impl Matcher for NothingMatcher {
@@ -27,4 +36,29 @@ impl Matcher for NothingMatcher {
             return None;
         }
     }
+
+    /// Find the compiled pattern in the given text, starting from the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        let content = self.original.find_at(text, start)?;
+        return Some(Match::new(content.start(), content.end(), content.as_str()));
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let pattern = PatternExplanation {
+            pattern: self.original.as_str().to_string(),
+            suggestion: Suggestion::Nothing,
+            literals: vec![],
+            min_length: min_length(self.original.as_str()),
+            max_length: None,
+        };
+
+        return Explanation { strategy: Strategy::Single(Suggestion::Nothing), patterns: vec![pattern], wu_manber: None };
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_NOTHING);
+        writer.write_str(self.original.as_str());
+    }
 }
@@ -1,50 +1,75 @@
-use regex::Regex;
+use crate::{
+    builder::Config,
+    matcher::Matcher,
+    preprocessor::Preprocessor,
+    serialize::{self, Reader, Writer, TAG_PREFIX},
+    types::{Error, Explanation, Match, PatternExplanation, Stats, Strategy, Suggestion}
+};
 
-use crate::{matcher::Matcher, types::Match};
-
-use super::PrefixMatcher;
+use super::{min_length, Counters, PrefixMatcher};
 
 impl PrefixMatcher {
     /// Create a new matcher with the supplied pattern.
-    pub fn new(pattern: &str) -> PrefixMatcher {
-        let original = Regex::new(pattern).unwrap();
-
-        // Char by char, construct the longest literal prefix fragment possible.
-        let mut prefix = String::from("");
-        for c in pattern.chars() {
-            if ['\\', '.', '[', '^', '$', '*', '+', '?', '(', '|', '{'].contains(&c) {
-                break;
-            }
+    pub fn new(pattern: &str, config: &Config) -> PrefixMatcher {
+        let original = config.compile(pattern).unwrap();
 
-            prefix.push(c);
-        }
+        // Construct the longest literal prefix fragment possible.
+        let prefix = Preprocessor::new(pattern, config).prefix();
 
         // Return with the result.
-        return PrefixMatcher { original, prefix };
+        return PrefixMatcher { original, prefix, counters: Counters::new(config.stats) };
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<PrefixMatcher, Error> {
+        let original = serialize::compile(&reader.read_str()?, reader.config())?;
+        let prefix = reader.read_str()?;
+        return Ok(PrefixMatcher { original, prefix, counters: Counters::new(reader.config().stats) });
     }
 }
 
 impl Matcher for PrefixMatcher {
     /// Find the compiled pattern in the given text.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
+        return self.find_at(text, 0);
+    }
+
+    /// Find the compiled pattern in the given text, starting from the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
         // Find a candidate based on our prefix string.
-        let candidate = text.find(&self.prefix);
-        if candidate.is_none() {
-            return None;
-        }
-
-        // Run the original matcher on the remainder of the text.
-        let start = candidate.unwrap();
-        let result = self.original.find(&text[start..]);
-
-        // Return the (correctly offset) result.
-        if let Some(content) = result {
-            let match_start = content.start() + start;
-            let match_end = content.end() + start;
-            let matched_text = &text[match_start..match_end];
-            return Some(Match::new(match_start, match_end, matched_text));
-        } else {
-            return None;
-        }
+        let candidate = text[start..].find(&self.prefix)? + start;
+
+        // Run the original matcher on the remainder of the text. The text before the
+        // candidate is kept, so that anchors are evaluated in the right context.
+        let content = self.original.find_at(text, candidate);
+        self.counters.record(content.is_some());
+
+        let content = content?;
+        return Some(Match::new(content.start(), content.end(), content.as_str()));
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let pattern = PatternExplanation {
+            pattern: self.original.as_str().to_string(),
+            suggestion: Suggestion::Prefix,
+            literals: vec![self.prefix.clone()],
+            min_length: min_length(self.original.as_str()),
+            max_length: None,
+        };
+
+        return Explanation { strategy: Strategy::Single(Suggestion::Prefix), patterns: vec![pattern], wu_manber: None };
+    }
+
+    /// Returns the counters of the prefix candidates, and of the ones confirmed by the pattern.
+    fn stats(&self) -> Stats {
+        return self.counters.stats();
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_PREFIX);
+        writer.write_str(self.original.as_str());
+        writer.write_str(&self.prefix);
     }
 }
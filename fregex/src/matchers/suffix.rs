@@ -0,0 +1,88 @@
+use crate::{
+    builder::Config,
+    matcher::Matcher,
+    preprocessor::Preprocessor,
+    serialize::{self, Reader, Writer, TAG_SUFFIX},
+    types::{Error, Explanation, Match, PatternExplanation, Stats, Strategy, Suggestion}
+};
+
+use super::{min_length, Counters, SuffixMatcher};
+
+impl SuffixMatcher {
+    /// Create a new matcher with the supplied pattern.
+    pub fn new(pattern: &str, config: &Config) -> SuffixMatcher {
+        let original = config.compile(pattern).unwrap();
+
+        // Construct the longest literal suffix fragment possible.
+        let suffix = Preprocessor::new(pattern, config).suffix();
+
+        // Return with the result.
+        return SuffixMatcher { original, suffix, terminator: config.terminator, counters: Counters::new(config.stats) };
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<SuffixMatcher, Error> {
+        let original = serialize::compile(&reader.read_str()?, reader.config())?;
+        let suffix = reader.read_str()?;
+        return Ok(SuffixMatcher { original, suffix, terminator: reader.terminator(), counters: Counters::new(reader.config().stats) });
+    }
+}
+
+impl Matcher for SuffixMatcher {
+    /// Find the compiled pattern in the given text.
+    fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
+        let mut offset = 0;
+
+        // Every match ends with the suffix, and is contained in a single line. As such,
+        // the first line that contains a match is also the first line with a suffix
+        // candidate that can be extended into a full match.
+        while let Some(pos) = text[offset..].find(&self.suffix) {
+            let candidate = offset + pos;
+
+            // Broaden the matching range to the entire line the candidate was found in.
+            let start = self.terminator.rfind(text, candidate).map_or(0, |p| p + 1);
+            let end = self.terminator.find(text, candidate).unwrap_or(text.len());
+
+            // Run the original matcher on the line, and return the (correctly offset) result.
+            let result = self.original.find(&text[start..end]);
+            self.counters.record(result.is_some());
+
+            if let Some(content) = result {
+                let match_start = content.start() + start;
+                let match_end = content.end() + start;
+                let matched_text = &text[match_start..match_end];
+                return Some(Match::new(match_start, match_end, matched_text));
+            }
+
+            // Else continue with the next line.
+            offset = end;
+        }
+
+        return None;
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let pattern = PatternExplanation {
+            pattern: self.original.as_str().to_string(),
+            suggestion: Suggestion::Suffix,
+            literals: vec![self.suffix.clone()],
+            min_length: min_length(self.original.as_str()),
+            max_length: None,
+        };
+
+        return Explanation { strategy: Strategy::Single(Suggestion::Suffix), patterns: vec![pattern], wu_manber: None };
+    }
+
+    /// Returns the counters of the suffix candidates, and of the ones confirmed by the pattern.
+    fn stats(&self) -> Stats {
+        return self.counters.stats();
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_SUFFIX);
+        writer.write_str(self.original.as_str());
+        writer.write_str(&self.suffix);
+    }
+}
@@ -1,73 +1,330 @@
 use crate::{
-    matchers::{LiteralMatcher, LongestMatcher, PrefixMatcher, NothingMatcher},
-    multimatchers::{LiteralMultiMatcher, LongestMultiMatcher, NothingMultiMatcher}, 
-    preprocessor::{Preprocessor, Suggestion},
-    types::{Error, Match}, MultiRegex, matcher::Matcher, RegexMatcher,
+    builder::Config,
+    matcher::Matcher,
+    matchers::{self, Boundary},
+    multimatchers::{wumanber::BLOCK_SIZE, HybridMultiMatcher, LiteralMultiMatcher, LongestMultiMatcher, NothingMultiMatcher},
+    preprocessor::Preprocessor,
+    serialize::{self, Reader, Writer, KIND_MULTI},
+    types::{Error, Explanation, Match, SetMatches, Stats, Strategy, Suggestion}, sealed, MultiRegex, RegexMatcher,
 };
 
+/// A single pattern of a `MultiRegex`, with the results of its preprocessing.
+pub(crate) struct PatternEntry {
+    /// The pattern string itself.
+    pattern: String,
+    /// The heuristic chosen for the pattern.
+    suggestion: Suggestion,
+    /// Whether the literal (or best fragment) of the pattern is long
+    /// enough to be used in the Wu-Manber tables.
+    filterable: bool,
+}
+
+impl PatternEntry {
+    /// Preprocesses the given pattern. May return an error, if the pattern is not valid.
+    /// Fixed strings are not preprocessed, they are stored escaped as literal patterns.
+    fn new(pattern: &str, config: &Config) -> Result<PatternEntry, Error> {
+        if config.fixed_strings {
+            let filterable = pattern.len() >= BLOCK_SIZE;
+            return Ok(PatternEntry { pattern: regex::escape(pattern), suggestion: Suggestion::Literal, filterable });
+        }
+
+        let preprocessor = Preprocessor::new(pattern, config);
+        let suggestion = preprocessor.determine_type()?;
+        let filterable = filterable(&preprocessor, suggestion);
+
+        return Ok(PatternEntry { pattern: pattern.to_string(), suggestion, filterable });
+    }
+}
+
+/// Determines whether the pattern can be located with the Wu-Manber algorithm: it must
+/// use the literal or the longest heuristic, with a literal (or best fragment) that is
+/// at least as long as the Wu-Manber blocks.
+pub(crate) fn filterable(preprocessor: &Preprocessor, suggestion: Suggestion) -> bool {
+    return match suggestion {
+        Suggestion::Literal => preprocessor.literal().len() >= BLOCK_SIZE,
+        Suggestion::Longest => preprocessor.best_fragment().len() >= BLOCK_SIZE,
+        _ => false
+    };
+}
+
 impl<'p> MultiRegex<'p> {
     /// Create a new regular expression matcher from the given patterns.
-    /// 
+    ///
     /// The function determines which internal matcher works best on the
     /// given patterns and instantiates it to be used during matching.
     pub fn new(patterns: &'p [&'p str]) -> Result<MultiRegex<'p>, Error> {
-        // Assert that at least one pattern is present
-        if patterns.len() == 0 {
-            panic!("No patterns were provided!");
+        return MultiRegex::with_config(patterns, &Config::default());
+    }
+
+    /// Create a new regular expression matcher from the given patterns and options.
+    pub(crate) fn with_config(patterns: &'p [&'p str], config: &Config) -> Result<MultiRegex<'p>, Error> {
+        // At least one pattern must be present. Patterns can only be removed from a compiled set.
+        if patterns.is_empty() {
+            return Err(Error::Empty);
         }
 
-        // Preprocess each pattern.
-        let types: Vec<Result<Suggestion, Error>> = patterns
-            .iter()
-            .map(|p| Preprocessor::new(p).determine_type())
-            .collect();
+        // Preprocess each pattern. If any one of them results in an error, return that error.
+        let mut entries = vec![];
+        for pattern in patterns {
+            entries.push(Some(PatternEntry::new(pattern, config)?));
+        }
+
+        let mut regex = MultiRegex {
+            matcher: Box::new(NothingMultiMatcher::new(&[], config)),
+            strategy: Strategy::NothingMulti,
+            patterns: entries,
+            ids: vec![],
+            config: config.clone(),
+        };
+        regex.rebuild();
+
+        return Ok(regex);
+    }
+
+    /// Adds a new pattern to the set, and returns its id. The compiled matcher is
+    /// extended in place, unless the pattern requires a different matching strategy
+    /// (or, for example, a shorter Wu-Manber block window), in which case it is rebuilt.
+    /// Matchers that check the boundaries of the matches are always rebuilt.
+    /// May return an error, if the pattern is not a valid pattern.
+    pub fn add(&mut self, pattern: &str) -> Result<usize, Error> {
+        let entry = PatternEntry::new(pattern, &self.config)?;
+        let suggestion = entry.suggestion;
+
+        let id = self.patterns.len();
+        self.patterns.push(Some(entry));
 
-        // If any one of the preprocessing runs resulted in an error, return that error.
-        for result in &types {
-            if let Err(reason) = result {
-                return Err(reason.to_owned());
+        // Try extending the current matcher if the strategy stays the same.
+        if self.choose_strategy() == self.strategy && self.matcher.insert(pattern, suggestion, &self.config) {
+            self.ids.push(Some(id));
+        } else {
+            self.rebuild();
+        }
+
+        return Ok(id);
+    }
+
+    /// Removes the pattern with the given id from the set. The ids of the other patterns
+    /// don't change. Returns false if no pattern with the given id is present.
+    pub fn remove(&mut self, id: usize) -> bool {
+        if !matches!(self.patterns.get(id), Some(Some(_))) {
+            return false;
+        }
+        self.patterns[id] = None;
+
+        // Try removing the pattern from the current matcher if the strategy stays the same.
+        let index = self.ids.iter().position(|i| *i == Some(id)).unwrap();
+        if self.choose_strategy() == self.strategy && self.matcher.remove(index) {
+            self.ids[index] = None;
+        } else {
+            self.rebuild();
+        }
+
+        return true;
+    }
+
+    /// Determines which patterns of the set match anywhere in the given text. Unlike
+    /// `find`, every pattern is reported, not only the one with the earliest match.
+    pub fn matches(&self, text: &str) -> SetMatches {
+        let mut matched = vec![false; self.patterns.len()];
+
+        // Translate the indices of the compiled matcher into pattern ids.
+        for (id, flag) in self.ids.iter().zip(self.matcher.matches(text)) {
+            if let Some(id) = id {
+                matched[*id] = flag;
             }
         }
 
+        return SetMatches::new(matched);
+    }
+
+    /// Returns an iterator over every overlapping match of the patterns in the given text,
+    /// paired with the id of the matching pattern. Every position a pattern matches at is
+    /// reported, with the match of the pattern starting there, so literal patterns are
+    /// reported at each of their occurrences. The matches are ordered by their start
+    /// positions first, and by their pattern ids second.
+    /// The matches are found lazily, as the iterator is advanced.
+    pub fn find_overlapping_iter<'r, 't: 'r>(&'r self, text: &'t str) -> impl Iterator<Item = (Match<'t>, usize)> + 'r {
+        // The indices of the compiled matcher increase with the pattern ids, so translating
+        // them keeps the order of the matches.
+        return self.matcher
+            .find_overlapping(text)
+            .filter_map(|(m, index)| self.ids[index].map(|id| (m, id)));
+    }
+
+    /// Returns the number of patterns in the set.
+    pub fn len(&self) -> usize {
+        return self.patterns.iter().filter(|p| p.is_some()).count();
+    }
+
+    /// Determines whether every pattern was removed from the set.
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    /// Determines the best matching strategy for the current patterns.
+    fn choose_strategy(&self) -> Strategy {
+        let entries: Vec<&PatternEntry> = self.patterns.iter().flatten().collect();
+
         // If only one pattern is present, use the proper single pattern matcher.
-        if patterns.len() == 1 {
-            let pattern = patterns.first().unwrap();
-            let matcher: Box<dyn Matcher + 'p> = match types.first().unwrap().as_ref().unwrap() {
-                Suggestion::Literal => Box::new(LiteralMatcher::new(pattern)),
-                Suggestion::Longest => Box::new(LongestMatcher::new(pattern)),
-                Suggestion::Prefix => Box::new(PrefixMatcher::new(pattern)),
-                Suggestion::Nothing => Box::new(NothingMatcher::new(pattern))
-            };
-
-            return Ok(MultiRegex { matcher });
+        if entries.len() == 1 {
+            return Strategy::Single(entries[0].suggestion);
         }
 
-        // If all of the patterns are literal, we can use the Wu-Manber matcher directly.
-        if types.iter().all(|t| t.clone().unwrap() == Suggestion::Literal) {
-            return Ok(MultiRegex { matcher: Box::new(LiteralMultiMatcher::new(patterns)) });
+        // The Wu-Manber algorithm can only be used for the patterns with a long enough literal.
+        // If only some of the patterns have one, the two groups are searched for separately.
+        if entries.is_empty() || entries.iter().all(|e| !e.filterable) {
+            return Strategy::NothingMulti;
+        }
+        if entries.iter().any(|e| !e.filterable) {
+            return Strategy::Hybrid;
         }
 
-        // If any one pattern cannot be used with the literal or the longest matcher,
-        // we'll run the naive algorithm that checks each pattern sequentially.
-        if types.iter().any(|t| [Suggestion::Nothing, Suggestion::Prefix].contains(t.as_ref().unwrap())) {
-            return Ok(MultiRegex { matcher: Box::new(NothingMultiMatcher::new(patterns)) });
+        // If all of the patterns are literal, we can use the Wu-Manber matcher directly.
+        if entries.iter().all(|e| e.suggestion == Suggestion::Literal) {
+            return Strategy::LiteralMulti;
         }
 
         // Otherwise, every pattern uses either the longest or the literal heuristics, but
         // we can't use the Wu-Manber matcher directly, as at least one pattern isn't literal.
-        return Ok(MultiRegex { matcher: Box::new(LongestMultiMatcher::new(patterns)) });
+        return Strategy::LongestMulti;
+    }
+
+    /// Compiles a new matcher from the current patterns with the given strategy.
+    fn compile(&self, strategy: Strategy) -> Box<dyn Matcher> {
+        let patterns: Vec<&str> = self.patterns
+            .iter()
+            .flatten()
+            .map(|e| e.pattern.as_str())
+            .collect();
+
+        let matcher: Box<dyn Matcher> = match strategy {
+            Strategy::Single(suggestion) => matchers::create(patterns[0], suggestion, &self.config),
+            Strategy::LiteralMulti => Box::new(LiteralMultiMatcher::new(&patterns, &self.config)),
+            Strategy::LongestMulti => Box::new(LongestMultiMatcher::new(&patterns, &self.config)),
+            Strategy::NothingMulti => Box::new(NothingMultiMatcher::new(&patterns, &self.config)),
+            Strategy::Hybrid => Box::new(HybridMultiMatcher::new(&patterns, &self.config)),
+        };
+
+        // The boundaries of the matches are checked by a wrapper around the compiled matcher.
+        return matchers::bound(matcher, &self.config);
+    }
+
+    /// Replaces the compiled matcher with a new one, built from the current patterns.
+    fn rebuild(&mut self) {
+        self.strategy = self.choose_strategy();
+        self.matcher = self.compile(self.strategy);
+        self.ids = self.patterns
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.is_some())
+            .map(|(id, _)| Some(id))
+            .collect();
+    }
+
+    /// Serializes the compiled matcher into a versioned binary format, that can be loaded
+    /// with `MultiRegex::from_bytes` without rebuilding the Wu-Manber tables. Removed
+    /// patterns are not written, so the loaded matcher numbers its patterns from zero.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(KIND_MULTI, &self.config);
+
+        // Patterns removed in place are still part of the compiled matcher.
+        if self.ids.iter().any(|i| i.is_none()) {
+            self.compile(self.strategy).serialize(&mut writer);
+        } else {
+            self.matcher.serialize(&mut writer);
+        }
+
+        return writer.into_bytes();
+    }
+
+    /// Loads a matcher serialized with `MultiRegex::to_bytes`. The stored patterns are
+    /// validated again, and an error is returned if the data is malformed. Patterns
+    /// added to the loaded matcher are compiled with the default options, except for
+    /// the boundaries of the matches, the line terminator and the multi-line mode, which are kept.
+    pub fn from_bytes(bytes: &[u8]) -> Result<MultiRegex<'static>, Error> {
+        let mut reader = Reader::new(bytes, KIND_MULTI)?;
+        let matcher = serialize::read_matcher(&mut reader, serialize::MULTI_REGEX_TAGS)?;
+        reader.finish()?;
+
+        // Recover the patterns from the description of the matcher,
+        // so that they don't have to be preprocessed again.
+        let explanation = matcher.explain();
+        let patterns: Vec<Option<PatternEntry>> = explanation.patterns
+            .into_iter()
+            .map(|p| Some(PatternEntry {
+                pattern: p.pattern,
+                suggestion: p.suggestion,
+                filterable: matches!(p.suggestion, Suggestion::Literal | Suggestion::Longest)
+                    && p.literals.first().is_some_and(|l| l.len() >= BLOCK_SIZE),
+            }))
+            .collect();
+
+        let config = Config {
+            whole_word: matcher.boundary() == Some(Boundary::Word),
+            whole_line: matcher.boundary() == Some(Boundary::Line),
+            ..reader.config().clone()
+        };
+
+        return Ok(MultiRegex {
+            matcher,
+            strategy: explanation.strategy,
+            ids: (0..patterns.len()).map(Some).collect(),
+            patterns,
+            config,
+        });
     }
 }
 
+impl sealed::Sealed for MultiRegex<'_> {}
+
 impl RegexMatcher for MultiRegex<'_> {
     /// Determines whether the given text contains any matches for the compiled patterns.
     fn is_match<'t>(&self, text: &'t str) -> bool {
         return self.matcher.find(text).is_some();
     }
-    
+
     /// Finds the first match of the compiled patterns present
     /// in the text, or returns None if no matches are found.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
         return self.matcher.find(text);
     }
+
+    /// Finds the first match of the compiled patterns present in the text,
+    /// paired with the id of the matching pattern.
+    fn find_with_id<'t>(&self, text: &'t str) -> Option<(Match<'t>, usize)> {
+        let (found, index) = self.matcher.find_indexed_at(text, 0, text.len())?;
+        return Some((found, self.ids[index]?));
+    }
+
+    /// Finds the first match of the compiled patterns starting at or after the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return self.matcher.find_at(text, start);
+    }
+
+    /// Finds the first match of the compiled patterns starting at or after the given offset,
+    /// paired with the id of the matching pattern.
+    fn find_with_id_at<'t>(&self, text: &'t str, start: usize) -> Option<(Match<'t>, usize)> {
+        let (found, index) = self.matcher.find_indexed_at(text, start, text.len())?;
+        return Some((found, self.ids[index]?));
+    }
+
+    /// Describes the matching strategy chosen for the compiled patterns.
+    /// Patterns removed from the set are not listed.
+    fn explain(&self) -> Explanation {
+        let mut explanation = self.matcher.explain();
+        explanation.patterns = explanation.patterns
+            .into_iter()
+            .zip(&self.ids)
+            .filter(|(_, id)| id.is_some())
+            .map(|(p, _)| p)
+            .collect();
+
+        return explanation;
+    }
+
+    /// Returns the counters of the candidates found by the prefilters of the compiled patterns.
+    /// The counters start from zero whenever the matcher is rebuilt with a new strategy.
+    fn stats(&self) -> Stats {
+        return self.matcher.stats();
+    }
 }
@@ -0,0 +1,235 @@
+use std::cmp::min;
+
+use crate::{
+    builder::Config,
+    matcher::{Matcher, Overlapping},
+    multimatcher::filterable,
+    preprocessor::Preprocessor,
+    serialize::{self, Reader, Writer, TAG_HYBRID_MULTI, TAG_LITERAL_MULTI, TAG_LONGEST_MULTI, TAG_NOTHING_MULTI},
+    types::{Error, Explanation, Match, Stats, Strategy, Suggestion}
+};
+
+use super::{merged::Merged, nothing::earlier, HybridMultiMatcher, LiteralMultiMatcher, LongestMultiMatcher, NothingMultiMatcher};
+
+/// The size of the first window the groups are searched in, in bytes.
+const WINDOW_SIZE: usize = 1 << 10;
+
+impl HybridMultiMatcher {
+    /// Create a new matcher with the supplied patterns. At least one pattern must be
+    /// usable with the Wu-Manber algorithm, and at least one must not be.
+    pub fn new(patterns: &[&str], config: &Config) -> HybridMultiMatcher {
+        let (mut filtered, mut others) = (vec![], vec![]);
+        let (mut filtered_ids, mut other_ids) = (vec![], vec![]);
+        let mut all_literal = true;
+
+        // Sort the patterns into the two groups.
+        for (index, pattern) in patterns.iter().enumerate() {
+            let preprocessor = Preprocessor::new(pattern, config);
+            let suggestion = preprocessor.determine_type().unwrap();
+
+            if filterable(&preprocessor, suggestion) {
+                all_literal &= suggestion == Suggestion::Literal;
+                filtered.push(*pattern);
+                filtered_ids.push(index);
+            } else {
+                others.push(*pattern);
+                other_ids.push(index);
+            }
+        }
+
+        // If every filtered pattern is literal, we can use the Wu-Manber matcher directly.
+        let filtered: Box<dyn Matcher> = if all_literal {
+            Box::new(LiteralMultiMatcher::new(&filtered, config))
+        } else {
+            Box::new(LongestMultiMatcher::new(&filtered, config))
+        };
+        let others = Box::new(NothingMultiMatcher::new(&others, config));
+
+        return HybridMultiMatcher { filtered, others, filtered_ids, other_ids };
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<HybridMultiMatcher, Error> {
+        let count = reader.read_length()?;
+        let (mut filtered_ids, mut other_ids) = (vec![], vec![]);
+
+        for index in 0..count {
+            if reader.read_bool()? {
+                filtered_ids.push(index);
+            } else {
+                other_ids.push(index);
+            }
+        }
+
+        let filtered = serialize::read_matcher(reader, &[TAG_LITERAL_MULTI, TAG_LONGEST_MULTI])?;
+        let others = serialize::read_matcher(reader, &[TAG_NOTHING_MULTI])?;
+
+        // The groups must be matched by the proper multimatchers, with the right pattern counts.
+        let (filtered_explanation, other_explanation) = (filtered.explain(), others.explain());
+        if !matches!(filtered_explanation.strategy, Strategy::LiteralMulti | Strategy::LongestMulti)
+            || other_explanation.strategy != Strategy::NothingMulti {
+            return Err(Error::Format("Invalid matcher type in a pattern group."));
+        }
+        if filtered_explanation.patterns.len() != filtered_ids.len()
+            || other_explanation.patterns.len() != other_ids.len() {
+            return Err(Error::Format("Group size differs from the pattern count."));
+        }
+
+        return Ok(HybridMultiMatcher { filtered, others, filtered_ids, other_ids });
+    }
+
+    /// Returns the number of patterns the matcher was compiled with, including removed ones.
+    fn len(&self) -> usize {
+        return self.filtered_ids.len() + self.other_ids.len();
+    }
+}
+
+impl Matcher for HybridMultiMatcher {
+    /// Finds any one of the compiled patterns in the given text.
+    fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, 0, text.len()).map(|(m, _)| m);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, start, text.len()).map(|(m, _)| m);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given
+    /// offset, and returns the index of the matching pattern too.
+    fn find_indexed_at<'t>(&self, text: &'t str, start: usize, limit: usize) -> Option<(Match<'t>, usize)> {
+        let limit = min(limit, text.len());
+        let mut window = WINDOW_SIZE;
+
+        // The groups are searched in growing windows, so that a group without nearby matches
+        // doesn't scan the rest of the text, if the other group matches early on.
+        loop {
+            let end = min(limit, start.saturating_add(window));
+            let filtered = self.filtered
+                .find_indexed_at(text, start, end)
+                .map(|(m, i)| (m, self.filtered_ids[i]));
+
+            // The other group is only needed up to the match of the filtered one.
+            let other_end = filtered.as_ref().map_or(end, |(m, _)| min(end, m.start()));
+            let others = self.others
+                .find_indexed_at(text, start, other_end)
+                .map(|(m, i)| (m, self.other_ids[i]));
+
+            // The earlier match is final, if the groups without a match were searched up to it.
+            let searched = match (&filtered, &others) {
+                (Some(_), Some(_)) => usize::MAX,
+                (Some(_), None) => other_end,
+                (None, _) => end,
+            };
+            let best = match (filtered, others) {
+                (Some(filtered), Some(others)) => earlier(Some(filtered), others),
+                (filtered, others) => filtered.or(others),
+            };
+
+            if best.as_ref().is_some_and(|(m, _)| m.start() <= searched) {
+                return best;
+            }
+            if end == limit {
+                return None;
+            }
+
+            window *= 2;
+        }
+    }
+
+    /// Finds every overlapping match of the compiled patterns in both groups.
+    /// The indices of each group increase with the indices of the patterns, so translating
+    /// them keeps the order of the matches of the group.
+    fn find_overlapping<'w, 't: 'w>(&'w self, text: &'t str) -> Overlapping<'w, 't> {
+        let filtered = self.filtered
+            .find_overlapping(text)
+            .map(|(m, i)| (m, self.filtered_ids[i]));
+        let others = self.others
+            .find_overlapping(text)
+            .map(|(m, i)| (m, self.other_ids[i]));
+
+        return Box::new(Merged::new(vec![Box::new(filtered), Box::new(others)]));
+    }
+
+    /// Determines which of the compiled patterns match in the given text.
+    fn matches(&self, text: &str) -> Vec<bool> {
+        let mut matched = vec![false; self.len()];
+
+        for (index, flag) in self.filtered_ids.iter().zip(self.filtered.matches(text)) {
+            matched[*index] = flag;
+        }
+        for (index, flag) in self.other_ids.iter().zip(self.others.matches(text)) {
+            matched[*index] = flag;
+        }
+
+        return matched;
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let filtered = self.filtered.explain();
+        let others = self.others.explain();
+
+        // Restore the original order of the patterns.
+        let mut patterns: Vec<_> = filtered.patterns
+            .into_iter()
+            .zip(&self.filtered_ids)
+            .chain(others.patterns.into_iter().zip(&self.other_ids))
+            .collect();
+        patterns.sort_by_key(|(_, index)| **index);
+
+        return Explanation {
+            strategy: Strategy::Hybrid,
+            patterns: patterns.into_iter().map(|(p, _)| p).collect(),
+            wu_manber: filtered.wu_manber,
+        };
+    }
+
+    /// Returns the counters of both groups of patterns.
+    fn stats(&self) -> Stats {
+        return self.filtered.stats() + self.others.stats();
+    }
+
+    /// Write the tag, the group of each pattern, and the matchers of the groups.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_HYBRID_MULTI);
+        writer.write_usize(self.len());
+
+        for index in 0..self.len() {
+            writer.write_bool(self.filtered_ids.contains(&index));
+        }
+
+        self.filtered.serialize(writer);
+        self.others.serialize(writer);
+    }
+
+    /// Add a pattern to the matcher of its group.
+    fn insert(&mut self, pattern: &str, suggestion: Suggestion, config: &Config) -> bool {
+        let index = self.len();
+        let preprocessor = Preprocessor::new(pattern, config);
+
+        if filterable(&preprocessor, suggestion) {
+            if !self.filtered.insert(pattern, suggestion, config) {
+                return false;
+            }
+            self.filtered_ids.push(index);
+        } else {
+            if !self.others.insert(pattern, suggestion, config) {
+                return false;
+            }
+            self.other_ids.push(index);
+        }
+
+        return true;
+    }
+
+    /// Remove a pattern from the matcher of its group.
+    fn remove(&mut self, index: usize) -> bool {
+        if let Some(position) = self.filtered_ids.iter().position(|i| *i == index) {
+            return self.filtered.remove(position);
+        }
+
+        let position = self.other_ids.iter().position(|i| *i == index).unwrap();
+        return self.others.remove(position);
+    }
+}
@@ -1,23 +1,137 @@
-use crate::matcher::Matcher;
-use crate::types::Match;
+use crate::builder::Config;
+use crate::matcher::{Matcher, Overlapping};
+use crate::preprocessor::Preprocessor;
+use crate::serialize::{Reader, Writer, TAG_LITERAL_MULTI};
+use crate::types::{Error, Explanation, Match, PatternExplanation, Stats, Strategy, Suggestion};
 
-use super::{LiteralMultiMatcher, wumanber::WuManber};
+use super::{LiteralMultiMatcher, wumanber::{WuManber, BLOCK_SIZE}};
 
 
 impl LiteralMultiMatcher {
     /// Create a new matcher with the supplied patterns.
-    pub fn new(patterns: &[&str]) -> LiteralMultiMatcher {
-        return LiteralMultiMatcher { matcher: WuManber::new(patterns, 2) };
+    pub fn new(patterns: &[&str], config: &Config) -> LiteralMultiMatcher {
+        // Resolve the escape sequences of each pattern.
+        let literals: Vec<String> = patterns
+            .iter()
+            .map(|p| Preprocessor::new(p, config).literal())
+            .collect();
+        let literals: Vec<&str> = literals.iter().map(|l| l.as_str()).collect();
+
+        return LiteralMultiMatcher {
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            matcher: WuManber::new(&literals, BLOCK_SIZE, config.stats)
+        };
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<LiteralMultiMatcher, Error> {
+        let count = reader.read_length()?;
+        let mut patterns = vec![];
+
+        for _ in 0..count {
+            patterns.push(reader.read_str()?);
+        }
+
+        // Every pattern must have been compiled into the Wu-Manber tables.
+        let matcher = WuManber::deserialize(reader)?;
+        if matcher.patterns().len() != patterns.len() {
+            return Err(Error::Format("Literal count differs from the pattern count."));
+        }
+
+        return Ok(LiteralMultiMatcher { patterns, matcher });
     }
 }
 
 impl Matcher for LiteralMultiMatcher {
     /// Finds any one of the compiled patterns in the given text.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
-        if let Some((content, _)) = self.matcher.find(text) {
-            return Some(content);
+        return self.find_indexed_at(text, 0, text.len()).map(|(m, _)| m);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, start, text.len()).map(|(m, _)| m);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given
+    /// offset, and returns the index of the matching pattern too. The search stops at the limit.
+    fn find_indexed_at<'t>(&self, text: &'t str, start: usize, limit: usize) -> Option<(Match<'t>, usize)> {
+        let (content, pattern_id) = self.matcher
+            .find_iter(&text[start..])
+            .until(limit.saturating_sub(start))
+            .next()?;
+        return Some((content.offset(start), pattern_id));
+    }
+
+    /// Finds every overlapping occurrence of the compiled patterns with a single pass.
+    fn find_overlapping<'w, 't: 'w>(&'w self, text: &'t str) -> Overlapping<'w, 't> {
+        return Box::new(self.matcher.find_iter(text));
+    }
+
+    /// Determines which of the compiled patterns are present in the given text,
+    /// with a single pass of the Wu-Manber algorithm.
+    fn matches(&self, text: &str) -> Vec<bool> {
+        let mut matched = vec![false; self.matcher.patterns().len()];
+
+        for (_, pattern_id) in self.matcher.find_iter(text) {
+            matched[pattern_id] = true;
         }
 
-        return None;
+        return matched;
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let patterns = self.patterns
+            .iter()
+            .zip(self.matcher.patterns())
+            .map(|(p, l)| PatternExplanation {
+                pattern: p.clone(),
+                suggestion: Suggestion::Literal,
+                literals: vec![l.clone()],
+                min_length: l.len(),
+                max_length: Some(l.len()),
+            })
+            .collect();
+
+        return Explanation { strategy: Strategy::LiteralMulti, patterns, wu_manber: Some(self.matcher.explain()) };
+    }
+
+    /// Returns the counters of the Wu-Manber algorithm, which finds the matches on its own.
+    fn stats(&self) -> Stats {
+        return self.matcher.stats();
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_LITERAL_MULTI);
+        writer.write_usize(self.patterns.len());
+
+        for pattern in &self.patterns {
+            writer.write_str(pattern);
+        }
+
+        self.matcher.serialize(writer);
+    }
+
+    /// Add a literal pattern to the Wu-Manber tables.
+    fn insert(&mut self, pattern: &str, suggestion: Suggestion, config: &Config) -> bool {
+        if suggestion != Suggestion::Literal {
+            return false;
+        }
+
+        let literal = Preprocessor::new(pattern, config).literal();
+        if self.matcher.insert(&literal).is_none() {
+            return false;
+        }
+
+        self.patterns.push(pattern.to_string());
+        return true;
+    }
+
+    /// Remove a pattern from the Wu-Manber tables.
+    fn remove(&mut self, index: usize) -> bool {
+        self.matcher.remove(index);
+        return true;
     }
 }
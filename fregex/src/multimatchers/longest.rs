@@ -1,14 +1,22 @@
-use crate::{matchers::LongestMatcher, matcher::Matcher, types::Match};
+use std::cmp::{max, min};
 
-use super::{LongestMultiMatcher, wumanber::WuManber};
+use crate::{
+    builder::Config,
+    matchers::LongestMatcher,
+    matcher::{Matcher, Overlapping},
+    serialize::{Reader, Writer, TAG_LONGEST_MULTI},
+    types::{Error, Explanation, Match, Stats, Strategy, Suggestion}
+};
+
+use super::{merged::Merged, LongestMultiMatcher, wumanber::{WuManber, BLOCK_SIZE}};
 
 impl LongestMultiMatcher {
     /// Create a new matcher with the supplied patterns.
-    pub fn new(patterns: &[&str]) -> LongestMultiMatcher {
+    pub fn new(patterns: &[&str], config: &Config) -> LongestMultiMatcher {
         // Build each matcher.
         let matchers: Vec<Box<LongestMatcher>> = patterns
             .iter()
-            .map(|p| Box::new(LongestMatcher::new(p)))
+            .map(|p| Box::new(LongestMatcher::new(p, config)))
             .collect();
 
         // Gather each best fragment.
@@ -18,53 +26,182 @@ impl LongestMultiMatcher {
             .collect();
 
         // Create Wu-Manber struct and return with a newly created matcher.
-        let best_matcher = WuManber::new(&best_fragments, 2);
-        return LongestMultiMatcher { matchers, best_matcher };
+        let best_matcher = WuManber::new(&best_fragments, BLOCK_SIZE, config.stats);
+        return LongestMultiMatcher { matchers, best_matcher, terminator: config.terminator };
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<LongestMultiMatcher, Error> {
+        let count = reader.read_length()?;
+        let mut matchers = vec![];
+
+        for _ in 0..count {
+            matchers.push(Box::new(LongestMatcher::deserialize(reader)?));
+        }
+
+        // Every fragment must have been compiled into the Wu-Manber tables.
+        let best_matcher = WuManber::deserialize(reader)?;
+        if best_matcher.patterns().len() != matchers.len() {
+            return Err(Error::Format("Fragment count differs from the pattern count."));
+        }
+
+        return Ok(LongestMultiMatcher { matchers, best_matcher, terminator: reader.terminator() });
+    }
+
+    /// Returns the last position a candidate can be found at, if it belongs to a match
+    /// starting at or before the given limit. Matches of known length contain their
+    /// candidates within their maximum length, others within their line.
+    fn candidate_limit(&self, text: &str, limit: usize) -> usize {
+        if limit >= text.len() {
+            return usize::MAX;
+        }
+
+        let max_length = self.matchers.iter().filter_map(|m| m.max_length()).max().unwrap_or(0);
+        let mut last = limit + max_length;
+
+        // The candidates of matches of unknown length may be anywhere in the line of the limit.
+        if self.matchers.iter().any(|m| m.max_length().is_none()) {
+            match self.terminator.find(text, limit) {
+                Some(line_end) => last = max(last, line_end),
+                None => return usize::MAX
+            }
+        }
+
+        return last;
     }
 }
 
 impl Matcher for LongestMultiMatcher {
     /// Finds any one of the compiled patterns in the given text.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, 0, text.len()).map(|(m, _)| m);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, start, text.len()).map(|(m, _)| m);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given
+    /// offset, and returns the index of the matching pattern too. The search stops at the
+    /// last candidate that may belong to a match starting before the limit.
+    fn find_indexed_at<'t>(&self, text: &'t str, start: usize, limit: usize) -> Option<(Match<'t>, usize)> {
         // The only difference in this function, and the single pattern longest matching one
         // is that this matcher finds candidates from multiple patterns, and we need to locate
-        // a potential match using the correct single pattern matcher.
-
-        // Create a mutable text slice and set global offset.
-        let mut text = text;
-        let mut global_offset: usize = 0;
-
-        // This loop searches for match candidates. If a candidate is found,
-        // but it is not a proper match, the start of the slice will be adjusted
-        // to after the candidate's end. We iterate until there's no more text to match.
-        while text.len() > 0 {
-            // Search for the best fragment. If it's not present, no match can be found.
-            let candidate = self.best_matcher.find(text);
-            if candidate.is_none() {
-                return None;
+        // a potential match using the correct single pattern matcher. The patterns contain
+        // no anchors, so the text before the offset can be sliced off.
+        let text = &text[start..];
+        let limit = self.candidate_limit(text, limit.saturating_sub(start));
+
+        // Every candidate is verified in order. A failed candidate of one pattern says
+        // nothing about the candidates of the other patterns, so none of them are skipped.
+        let mut best: Option<(Match<'t>, usize)> = None;
+        let mut best_limit = limit;
+
+        for (coords, pattern_id) in self.best_matcher.find_iter(text).until(limit) {
+            // A match may start before its candidate, so once a match is found, the
+            // candidates that may belong to an earlier match are verified too.
+            if coords.start() > best_limit {
+                break;
+            }
+
+            let (result, _) = self.matchers[pattern_id].locate_near(text, coords.start());
+            if let Some(content) = result {
+                if best.as_ref().is_none_or(|(m, id)| (content.start(), pattern_id) < (m.start(), *id)) {
+                    best_limit = min(best_limit, self.candidate_limit(text, content.start()));
+                    best = Some((content, pattern_id));
+                }
             }
+        }
+
+        return best.map(|(content, pattern_id)| (content.offset(start), pattern_id));
+    }
+
+    /// Finds every overlapping match of the compiled patterns. The candidates are found with
+    /// a single pass of the Wu-Manber algorithm, and only the patterns with at least one
+    /// candidate are searched for with their own matchers.
+    fn find_overlapping<'w, 't: 'w>(&'w self, text: &'t str) -> Overlapping<'w, 't> {
+        let mut candidates = vec![false; self.matchers.len()];
+        for (_, pattern_id) in self.best_matcher.find_iter(text) {
+            candidates[pattern_id] = true;
+        }
 
-            // Destructure candidate coordinates and pattern id.
-            let (coords, pattern_id) = candidate.unwrap();
+        let sources: Vec<Overlapping<'w, 't>> = self.matchers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| candidates[*i])
+            .map(|(pattern_id, matcher)| -> Overlapping<'w, 't> {
+                return Box::new(matcher.find_overlapping(text).map(move |(m, _)| (m, pattern_id)));
+            })
+            .collect();
+
+        return Box::new(Merged::new(sources));
+    }
 
-            // Locate the possible match.
-            let (result, end) = self.matchers[pattern_id].locate_near(text, coords.start() as usize);
+    /// Determines which of the compiled patterns match in the given text. The candidates of
+    /// every pattern are found with a single pass of the Wu-Manber algorithm, and each
+    /// candidate is verified with the matcher of its own pattern.
+    fn matches(&self, text: &str) -> Vec<bool> {
+        let mut matched = vec![false; self.matchers.len()];
 
-            // If we found something, return with the match.
-            if result.is_some() {
-                let content = result.unwrap();
-                let match_start = content.start() + global_offset;
-                let match_end = content.end() + global_offset;
-                let matched_text = content.as_str();
-                return Some(Match::new(match_start, match_end, matched_text));
+        for (coords, pattern_id) in self.best_matcher.find_iter(text) {
+            // Patterns that already matched don't need to be verified again.
+            if !matched[pattern_id] {
+                matched[pattern_id] = self.matchers[pattern_id].locate_near(text, coords.start()).0.is_some();
             }
+        }
+
+        return matched;
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let patterns = self.matchers
+            .iter()
+            .flat_map(|m| m.explain().patterns)
+            .collect();
 
-            // Else adjust search range, and continue with the next iteration.
-            text = &text[end..];
-            global_offset += end;
+        return Explanation { strategy: Strategy::LongestMulti, patterns, wu_manber: Some(self.best_matcher.explain()) };
+    }
+
+    /// Returns the counters of the Wu-Manber algorithm finding the fragments, and the
+    /// ones of each single pattern matcher verifying them.
+    fn stats(&self) -> Stats {
+        return self.matchers.iter().fold(self.best_matcher.stats(), |stats, m| stats + m.stats());
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_LONGEST_MULTI);
+        writer.write_usize(self.matchers.len());
+
+        for matcher in &self.matchers {
+            matcher.serialize_state(writer);
         }
 
-        // Return none if we ran out of text to search.
-        return None;
+        self.best_matcher.serialize(writer);
+    }
+
+    /// Add a pattern that can be used with the longest heuristic, by
+    /// inserting its best fragment into the Wu-Manber tables.
+    fn insert(&mut self, pattern: &str, suggestion: Suggestion, config: &Config) -> bool {
+        if suggestion != Suggestion::Literal && suggestion != Suggestion::Longest {
+            return false;
+        }
+
+        let matcher = LongestMatcher::new(pattern, config);
+        if self.best_matcher.insert(matcher.best_fragment()).is_none() {
+            return false;
+        }
+
+        self.matchers.push(Box::new(matcher));
+        return true;
+    }
+
+    /// Remove the fragment of a pattern from the Wu-Manber tables. The single pattern
+    /// matcher is kept, so that the indices of the other patterns don't change.
+    fn remove(&mut self, index: usize) -> bool {
+        self.best_matcher.remove(index);
+        return true;
     }
 }
@@ -0,0 +1,40 @@
+use std::iter::Peekable;
+
+use crate::{matcher::Overlapping, types::Match};
+
+/// An iterator that merges the overlapping matches of several matchers. Each of the merged
+/// iterators must report its matches ordered by their start positions first, and by their
+/// pattern indices second, and then the merged matches are ordered the same way.
+pub struct Merged<'w, 't> {
+    /// The overlapping matches of each merged matcher.
+    sources: Vec<Peekable<Overlapping<'w, 't>>>,
+}
+
+impl<'w, 't> Merged<'w, 't> {
+    /// Creates a new iterator over the matches of every given iterator.
+    pub fn new(sources: Vec<Overlapping<'w, 't>>) -> Self {
+        return Merged { sources: sources.into_iter().map(|s| s.peekable()).collect() };
+    }
+}
+
+impl<'w, 't> Iterator for Merged<'w, 't> {
+    type Item = (Match<'t>, usize);
+
+    /// Returns the earliest of the next matches of the merged iterators.
+    fn next(&mut self) -> Option<(Match<'t>, usize)> {
+        let mut earliest: Option<(usize, (usize, usize))> = None;
+
+        // Find the source with the next match of the lowest start and pattern index.
+        for (i, source) in self.sources.iter_mut().enumerate() {
+            if let Some((m, index)) = source.peek() {
+                let key = (m.start(), *index);
+
+                if earliest.is_none_or(|(_, best)| key < best) {
+                    earliest = Some((i, key));
+                }
+            }
+        }
+
+        return self.sources[earliest?.0].next();
+    }
+}
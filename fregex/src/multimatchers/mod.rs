@@ -1,15 +1,21 @@
-use crate::{matcher::Matcher, matchers::LongestMatcher};
+use regex_automata::meta;
+
+use crate::{builder::{Config, LineTerminator}, matcher::Matcher, matchers::{Counters, LongestMatcher}};
 
 use self::wumanber::WuManber;
 
+pub mod hybrid;
 pub mod literal;
 pub mod longest;
+pub mod merged;
 pub mod nothing;
 pub mod wumanber;
 
 /// A literal pattern matcher implementation. Technically
 /// nothing more than a wrapper for the Wu-Manber search algorithm.
 pub struct LiteralMultiMatcher {
+    /// The original patterns, before their escape sequences were resolved.
+    patterns: Vec<String>,
     /// The compiled Wu-Manber search algorithm instance.
     matcher: WuManber,
 }
@@ -20,12 +26,50 @@ pub struct LongestMultiMatcher {
     /// One matcher for each input pattern.
     matchers: Vec<Box<LongestMatcher>>,
     /// The compiled Wu-Manber instance for the best pattern fragments.
-    best_matcher: WuManber
+    best_matcher: WuManber,
+    /// How the lines of the text end. Matches of unknown length are confined to a line.
+    terminator: LineTerminator,
 }
 
 /// A multimatcher implementation that can process any and every pattern,
 /// but the resulting search may not be as quick as other implementations.
-pub struct NothingMultiMatcher<'p> {
+/// 
+/// The text is scanned once for every pattern: patterns with a long enough literal
+/// prefix share a Wu-Manber prefilter, while the rest are compiled into one automaton.
+pub struct NothingMultiMatcher {
     /// One matcher for each input pattern.
-    matchers: Vec<Box<dyn Matcher + 'p>>
+    matchers: Vec<Box<dyn Matcher>>,
+    /// Whether each matcher is still used, or its pattern was removed.
+    live: Vec<bool>,
+    /// The compiled Wu-Manber instance for the prefixes of the prefix patterns.
+    prefixes: Option<WuManber>,
+    /// The prefix patterns compiled into a single automaton, used to verify
+    /// the candidates of the prefilter with an anchored search.
+    prefixed: Option<meta::Regex>,
+    /// The index of the pattern of each prefix in the Wu-Manber instance.
+    prefix_ids: Vec<usize>,
+    /// The rest of the patterns, compiled into a single automaton. None if the automaton
+    /// couldn't be compiled (for example, because it's too large), in which case
+    /// the patterns are searched for one by one.
+    combined: Option<meta::Regex>,
+    /// The index of each pattern of the combined automaton.
+    combined_ids: Vec<usize>,
+    /// The counters of the prefix candidates verified by the automaton of the prefix patterns.
+    counters: Counters,
+    /// The line terminator and the multi-line mode used to compile the automata.
+    config: Config,
+}
+
+/// A multimatcher implementation that splits the patterns into two groups: the ones
+/// that can be located with the Wu-Manber algorithm, and the rest. Each group is
+/// searched with its own matcher, and the earlier match of the two is returned.
+pub struct HybridMultiMatcher {
+    /// The matcher of the literal and longest patterns, using the Wu-Manber algorithm.
+    filtered: Box<dyn Matcher>,
+    /// The matcher of the rest of the patterns.
+    others: Box<dyn Matcher>,
+    /// The index of each pattern of the filtered group.
+    filtered_ids: Vec<usize>,
+    /// The index of each pattern of the other group.
+    other_ids: Vec<usize>,
 }
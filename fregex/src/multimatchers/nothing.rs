@@ -1,49 +1,278 @@
+use std::cmp::min;
+
+use regex_automata::{meta, nfa::thompson::WhichCaptures, Anchored, Input, PatternID};
+
 use crate::{
-    matcher::Matcher,
-    matchers::{LiteralMatcher, LongestMatcher, PrefixMatcher, NothingMatcher},
-    preprocessor::{Suggestion, Preprocessor},
-    types::{Match}
+    builder::Config,
+    matcher::{Matcher, Overlapping},
+    matchers::{self, Counters},
+    preprocessor::Preprocessor,
+    serialize::{self, Reader, Writer, TAG_NOTHING_MULTI},
+    types::{Error, Explanation, Match, Stats, Strategy, Suggestion}
 };
 
-use super::NothingMultiMatcher;
+use super::{merged::Merged, NothingMultiMatcher, wumanber::{WuManber, BLOCK_SIZE}};
 
 
-impl<'p> NothingMultiMatcher<'p> {
+impl NothingMultiMatcher {
     /// Create a new matcher with the supplied patterns.
-    pub fn new(patterns: &'p [&'p str]) -> NothingMultiMatcher<'p> {
-        let mut matchers: Vec<Box<dyn Matcher + 'p>> = vec![];
+    pub fn new(patterns: &[&str], config: &Config) -> NothingMultiMatcher {
+        let mut matchers: Vec<Box<dyn Matcher>> = vec![];
 
         // For each pattern, determine type and construct matcher.
         for pattern in patterns {
-            let suggestion = Preprocessor::new(pattern).determine_type().unwrap();
-            let matcher: Box<dyn Matcher + 'p> = match suggestion {
-                Suggestion::Literal => Box::new(LiteralMatcher::new(pattern)),
-                Suggestion::Longest => Box::new(LongestMatcher::new(pattern)),
-                Suggestion::Prefix => Box::new(PrefixMatcher::new(pattern)),
-                Suggestion::Nothing => Box::new(NothingMatcher::new(pattern))
-            };
-            matchers.push(matcher);
+            let suggestion = Preprocessor::new(pattern, config).determine_type().unwrap();
+            matchers.push(matchers::create(pattern, suggestion, config));
+        }
+
+        return NothingMultiMatcher::from_matchers(matchers, config);
+    }
+
+    /// Read a matcher written by `Matcher::serialize`, without its tag.
+    pub fn deserialize(reader: &mut Reader) -> Result<NothingMultiMatcher, Error> {
+        let count = reader.read_length()?;
+        let mut matchers = vec![];
+
+        for _ in 0..count {
+            matchers.push(serialize::read_matcher(reader, serialize::SINGLE_TAGS)?);
+        }
+
+        return Ok(NothingMultiMatcher::from_matchers(matchers, reader.config()));
+    }
+
+    /// Create a new matcher from the single pattern matchers of each pattern,
+    /// compiled with the line terminator and the multi-line mode of the options.
+    fn from_matchers(matchers: Vec<Box<dyn Matcher>>, config: &Config) -> NothingMultiMatcher {
+        let live = vec![true; matchers.len()];
+
+        let mut matcher = NothingMultiMatcher {
+            matchers,
+            live,
+            prefixes: None,
+            prefixed: None,
+            prefix_ids: vec![],
+            combined: None,
+            combined_ids: vec![],
+            counters: Counters::new(config.stats),
+            config: config.clone(),
+        };
+        matcher.index();
+
+        return matcher;
+    }
+
+    /// Builds the shared prefilter of the prefix patterns and the combined automaton
+    /// of the other patterns from the patterns that are still used.
+    fn index(&mut self) {
+        let (mut prefixes, mut prefixed) = (vec![], vec![]);
+        let mut others = vec![];
+        self.prefix_ids.clear();
+        self.combined_ids.clear();
+
+        // Sort the patterns into the two groups.
+        for (index, matcher) in self.matchers.iter().enumerate().filter(|(i, _)| self.live[*i]) {
+            let pattern = matcher.explain().patterns.remove(0);
+
+            if pattern.suggestion == Suggestion::Prefix && pattern.literals[0].len() >= BLOCK_SIZE {
+                prefixes.push(pattern.literals[0].clone());
+                prefixed.push(pattern.pattern);
+                self.prefix_ids.push(index);
+            } else {
+                others.push(pattern.pattern);
+                self.combined_ids.push(index);
+            }
+        }
+
+        // If the prefix patterns can't be compiled together, they are searched for with the others.
+        self.prefixed = compile(&prefixed, &self.config);
+        if self.prefixed.is_none() {
+            others.append(&mut prefixed);
+            self.combined_ids.append(&mut self.prefix_ids);
+            prefixes.clear();
+        }
+
+        let prefixes: Vec<&str> = prefixes.iter().map(|p| p.as_str()).collect();
+        self.prefixes = if prefixes.is_empty() { None } else { Some(WuManber::new(&prefixes, BLOCK_SIZE, self.config.stats)) };
+        self.combined = compile(&others, &self.config);
+    }
+
+    /// Finds the earliest match of the prefix patterns that starts between the given
+    /// offset and limit. Returns the match and the index of its pattern.
+    fn find_prefixed<'t>(&self, text: &'t str, start: usize, limit: usize) -> Option<(Match<'t>, usize)> {
+        let (prefixes, prefixed) = (self.prefixes.as_ref()?, self.prefixed.as_ref()?);
+
+        // Every match starts with the prefix of its pattern, so the candidates are checked
+        // in order with an anchored search of their own pattern. The candidates of the same
+        // position are ordered by their pattern indices, so the first match is the best one.
+        for (coords, prefix_id) in prefixes.find_iter(&text[start..]).until(limit.saturating_sub(start)) {
+            let candidate = start + coords.start();
+            let input = Input::new(text)
+                .range(candidate..)
+                .anchored(Anchored::Pattern(PatternID::must(prefix_id)));
+
+            let result = prefixed.search(&input);
+            self.counters.record(result.is_some());
+
+            if let Some(result) = result {
+                let matched = Match::new(result.start(), result.end(), &text[result.range()]);
+                return Some((matched, self.prefix_ids[prefix_id]));
+            }
         }
-        
-        return NothingMultiMatcher { matchers };
+
+        return None;
     }
+
+    /// Finds the earliest match of the other patterns, starting from the given offset.
+    /// Returns the match and the index of its pattern.
+    fn find_combined<'t>(&self, text: &'t str, start: usize) -> Option<(Match<'t>, usize)> {
+        // If the automaton couldn't be compiled, try matching each pattern separately.
+        let combined = match &self.combined {
+            Some(combined) => combined,
+            None => {
+                return self.combined_ids
+                    .iter()
+                    .filter_map(|index| self.matchers[*index].find_at(text, start).map(|m| (m, *index)))
+                    .fold(None, earlier);
+            }
+        };
+
+        // The text before the offset is kept, so that anchors are evaluated in the right context.
+        let result = combined.search(&Input::new(text).range(start..))?;
+        let matched = Match::new(result.start(), result.end(), &text[result.range()]);
+
+        return Some((matched, self.combined_ids[result.pattern().as_usize()]));
+    }
+}
+
+/// Compiles the given patterns into a single automaton, with the line terminator and the
+/// multi-line mode of the options. Returns None if there are no patterns, or if they
+/// couldn't be compiled together.
+fn compile(patterns: &[String], config: &Config) -> Option<meta::Regex> {
+    if patterns.is_empty() {
+        return None;
+    }
+
+    // The reverse search of an automaton of multiple patterns may end at the match of
+    // another pattern when the anchors match around a line terminator other than a line
+    // feed, which the library-supplied matcher asserts against. Such patterns are
+    // searched for one by one instead.
+    if config.multi_line && config.terminator.byte() != b'\n' {
+        return None;
+    }
+
+    // The matches are only needed as a whole, so no capture groups are tracked.
+    return meta::Regex::builder()
+        .configure(config.terminator.configure(meta::Regex::config().which_captures(WhichCaptures::Implicit)))
+        .syntax(config.syntax())
+        .build_many(patterns)
+        .ok();
 }
 
-impl<'p> Matcher for NothingMultiMatcher<'p> {
+/// Chooses the match that starts earlier. On equal starts, the
+/// match of the pattern with the lower index is preferred.
+pub(super) fn earlier<'t>(best: Option<(Match<'t>, usize)>, other: (Match<'t>, usize)) -> Option<(Match<'t>, usize)> {
+    return match best {
+        Some(best) if (best.0.start(), best.1) <= (other.0.start(), other.1) => Some(best),
+        _ => Some(other)
+    };
+}
+
+impl Matcher for NothingMultiMatcher {
     /// Finds any one of the compiled patterns in the given text.
     fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
-        let mut best_match: Option<Match<'t>> = None;
+        return self.find_at(text, 0);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given offset.
+    fn find_at<'t>(&self, text: &'t str, start: usize) -> Option<Match<'t>> {
+        return self.find_indexed_at(text, start, text.len()).map(|(m, _)| m);
+    }
+
+    /// Finds any one of the compiled patterns in the given text, starting from the given
+    /// offset, and returns the index of the matching pattern too. The prefix candidates
+    /// are only checked up to the limit.
+    fn find_indexed_at<'t>(&self, text: &'t str, start: usize, limit: usize) -> Option<(Match<'t>, usize)> {
+        // Both groups are searched with a single pass, and the earlier match is returned.
+        // The prefix candidates after the match of the other patterns can be skipped.
+        let combined = self.find_combined(text, start);
+        let combined_start = combined.map_or(text.len(), |(m, _)| m.start());
+        let prefixed = self.find_prefixed(text, start, min(limit, combined_start));
+
+        return match (prefixed, combined) {
+            (Some(prefixed), Some(combined)) => earlier(Some(prefixed), combined),
+            (Some(prefixed), None) => Some(prefixed),
+            // Prefix matches may precede the other match if the candidates were cut off.
+            (None, combined) if combined_start <= limit => combined,
+            (None, _) => None,
+        };
+    }
+
+    /// Finds every overlapping match of the compiled patterns, by running each matcher separately.
+    fn find_overlapping<'w, 't: 'w>(&'w self, text: &'t str) -> Overlapping<'w, 't> {
+        let sources: Vec<Overlapping<'w, 't>> = self.matchers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.live[*i])
+            .map(|(pattern_id, matcher)| -> Overlapping<'w, 't> {
+                return Box::new(matcher.find_overlapping(text).map(move |(m, _)| (m, pattern_id)));
+            })
+            .collect();
+
+        return Box::new(Merged::new(sources));
+    }
+
+    /// Determines which of the compiled patterns match in the given text,
+    /// by running each matcher separately.
+    fn matches(&self, text: &str) -> Vec<bool> {
+        return self.matchers
+            .iter()
+            .zip(&self.live)
+            .map(|(matcher, live)| *live && matcher.find(text).is_some())
+            .collect();
+    }
+
+    /// Describe the strategy used by the matcher.
+    fn explain(&self) -> Explanation {
+        let patterns = self.matchers
+            .iter()
+            .flat_map(|m| m.explain().patterns)
+            .collect();
+
+        let wu_manber = self.prefixes.as_ref().map(|p| p.explain());
+        return Explanation { strategy: Strategy::NothingMulti, patterns, wu_manber };
+    }
+
+    /// Returns the counters of the prefix candidates, and of the ones confirmed by their
+    /// patterns, with the ones of the Wu-Manber algorithm finding the prefixes.
+    fn stats(&self) -> Stats {
+        let prefixes = self.prefixes.as_ref().map_or(Stats::default(), |p| p.stats());
+        return self.matchers.iter().fold(prefixes + self.counters.stats(), |stats, m| stats + m.stats());
+    }
+
+    /// Write the tag and the precomputed state of the matcher.
+    fn serialize(&self, writer: &mut Writer) {
+        writer.write_u8(TAG_NOTHING_MULTI);
+        writer.write_usize(self.matchers.len());
 
-        // Try matching each pattern, and store the match with the earliest start.
         for matcher in &self.matchers {
-            if let Some(result) = matcher.find(text) {
-                if best_match.is_none() || best_match.unwrap().start() > result.start() {
-                    best_match = Some(result);
-                }
-            }
+            matcher.serialize(writer);
         }
+    }
+
+    /// Add a pattern with its own single pattern matcher. The shared prefilter and
+    /// the combined automaton are rebuilt, but the other matchers are kept.
+    fn insert(&mut self, pattern: &str, suggestion: Suggestion, config: &Config) -> bool {
+        self.matchers.push(matchers::create(pattern, suggestion, config));
+        self.live.push(true);
+        self.index();
+        return true;
+    }
 
-        // Return with the best match. May be none if none of the matchers matched.
-        return best_match;
+    /// Stop using the matcher of a pattern. The matcher itself is kept, so
+    /// that the indices of the other patterns don't change.
+    fn remove(&mut self, index: usize) -> bool {
+        self.live[index] = false;
+        self.index();
+        return true;
     }
 }
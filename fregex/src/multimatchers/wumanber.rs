@@ -1,20 +1,47 @@
-use std::{cmp::{max, min_by, min}, collections::HashMap};
-use crate::types::Match;
+use std::{cmp::{max, min_by}, collections::{BTreeMap, HashMap}};
+
+use crate::{matchers::Counters, serialize::{Reader, Writer}, types::{Error, Match, Stats, WuManberExplanation}};
+
+/// The block size used by the multimatchers.
+pub const BLOCK_SIZE: usize = 2;
 
 /// Wu-Manber compilation struct.
 pub struct WuManber {
     /// A reference to every pattern, necessary during the searching phase.
     patterns: Vec<String>,
-    /// Shift (or jump) table for each b-long block present in the patterns.
-    shift_table: HashMap<String, usize>,
+    /// Whether each pattern is still present in the tables, or it was removed.
+    live: Vec<bool>,
+    /// Shift (or jump) table for each b-long block present in the patterns. The blocks
+    /// are compared as bytes, as they may split the multi-byte characters of the patterns.
+    shift_table: HashMap<Vec<u8>, usize>,
+    /// The number of blocks of the live patterns with each shift value, for each block. The
+    /// shift value of a block is the smallest one, so that it can be updated without scanning
+    /// the other patterns when a pattern is removed.
+    shift_counts: HashMap<Vec<u8>, BTreeMap<usize, usize>>,
     /// Pattern prefix table for each pattern that starts with the key block.
-    prefix_table: HashMap<String, Vec<PrefixHash>>,
+    prefix_table: HashMap<Vec<u8>, Vec<PrefixHash>>,
     /// The length of the shortest pattern.
     min_length: usize,
     /// The default shift value, when a block was not present in the patterns.
     default_shift: usize,
     /// The "B" magic constant that represents the block size. 2 or 3 should be used.
-    b: usize
+    b: usize,
+    /// The counters of the positions where a whole pattern was compared with the text.
+    counters: Counters,
+}
+
+/// An iterator over every occurrence of the patterns of a Wu-Manber struct in a text.
+pub struct Occurrences<'w, 't> {
+    /// The compiled Wu-Manber struct.
+    matcher: &'w WuManber,
+    /// The text that is searched.
+    text: &'t str,
+    /// The end of the next window to check.
+    pos: usize,
+    /// The last start position of the occurrences that are reported.
+    limit: usize,
+    /// The verified occurrences of the last window that haven't been returned yet.
+    pending: Vec<(Match<'t>, usize)>,
 }
 
 /// Prefix hash struct used in the Wu-Manber searching phase.
@@ -22,13 +49,14 @@ struct PrefixHash {
     /// Which pattern the prefix was created from.
     pattern_id: usize,
     /// What the prefix is.
-    prefix: String,
+    prefix: Vec<u8>,
 }
 
 impl WuManber {
     /// Creates a new Wu-Manber search struct with the given patterns. The b argument
     /// can be used to define the block size used. Usually, 2 or 3 is recommended.
-    pub fn new(patterns: &[&str], b: usize) -> WuManber {
+    /// The candidates are only counted if the stats argument is set.
+    pub fn new(patterns: &[&str], b: usize, stats: bool) -> WuManber {
         // Create variables for commonly used numbers below.
         let min_length = patterns
             .iter()
@@ -37,82 +65,309 @@ impl WuManber {
             .len();
 
         if min_length < b {
-            // TODO Handle this case - by default, WM shouldn't be used in this scenario.
+            // The multimatchers only use this algorithm with long enough patterns.
             panic!("Minimum pattern length used in WM algorithm is too small! ('{:?}')", patterns);
         }
 
-        // Create default shift and initialize block shift table.
+        // Create default shift and initialize the empty tables.
         let default_shift = min_length + 1 - b;
-        let mut shift_table: HashMap<String, usize> = HashMap::new();
-        let mut prefix_table: HashMap<String, Vec<PrefixHash>> = HashMap::new();
+        let mut matcher = WuManber {
+            patterns: vec![],
+            live: vec![],
+            shift_table: HashMap::new(),
+            shift_counts: HashMap::new(),
+            prefix_table: HashMap::new(),
+            min_length,
+            default_shift,
+            b,
+            counters: Counters::new(stats)
+        };
 
         // Read patterns, and set block shift values.
-        for (i, pattern) in patterns.iter().enumerate() {
-            for j in (b ..= min_length).rev() {
-                let block = &pattern[j-b ..= j-1];
-                
-                let this_shift = min_length - j;
-                let stored_shift = shift_table.get(block).unwrap_or(&this_shift).to_owned();
-
-                shift_table.insert(block.to_string(), min(this_shift, stored_shift));
-
-                // For each pattern, we also store its prefix once to speed up potential match verification.
-                if j == min_length {
-                    let hash = PrefixHash {
-                        pattern_id: i,
-                        prefix: pattern[0..b].to_string()
-                    };
-                    
-                    if prefix_table.contains_key(block) {
-                        prefix_table.get_mut(block).unwrap().push(hash);
-                    } else {
-                        prefix_table.insert(block.to_string(), vec![hash]);
-                    }
+        for pattern in patterns {
+            matcher.insert(pattern);
+        }
+
+        // Return with the compiled struct.
+        return matcher;
+    }
+
+    /// Adds a new pattern to the compiled tables, and returns its id. Returns None if the
+    /// pattern is shorter than the current minimum length, as every shift value would change.
+    pub fn insert(&mut self, pattern: &str) -> Option<usize> {
+        if pattern.len() < self.min_length {
+            return None;
+        }
+
+        let id = self.patterns.len();
+        let (b, min_length) = (self.b, self.min_length);
+        let bytes = pattern.as_bytes();
+
+        self.count_shifts(bytes);
+
+        // For each pattern, we also store its prefix once to speed up potential match verification.
+        let hash = PrefixHash {
+            pattern_id: id,
+            prefix: bytes[0..b].to_vec()
+        };
+        self.prefix_table.entry(bytes[min_length-b .. min_length].to_vec()).or_default().push(hash);
+
+        // Copy the pattern to save in the struct.
+        self.patterns.push(pattern.to_string());
+        self.live.push(true);
+
+        return Some(id);
+    }
+
+    /// Counts the shift value of each block of a pattern, and keeps the smallest
+    /// one of each block in the shift table.
+    fn count_shifts(&mut self, pattern: &[u8]) {
+        let (b, min_length) = (self.b, self.min_length);
+
+        for j in b ..= min_length {
+            let block = &pattern[j-b .. j];
+
+            let counts = self.shift_counts.entry(block.to_vec()).or_default();
+            *counts.entry(min_length - j).or_default() += 1;
+            self.shift_table.insert(block.to_vec(), *counts.first_key_value().unwrap().0);
+        }
+    }
+
+    /// Removes the pattern with the given id from the compiled tables. The ids
+    /// of the remaining patterns are left unchanged.
+    pub fn remove(&mut self, id: usize) {
+        if !self.live[id] {
+            return;
+        }
+        self.live[id] = false;
+
+        let (b, min_length) = (self.b, self.min_length);
+        let pattern = self.patterns[id].as_bytes();
+
+        // Remove the prefix entry of the pattern.
+        let last_block = &pattern[min_length-b .. min_length];
+        if let Some(hashes) = self.prefix_table.get_mut(last_block) {
+            hashes.retain(|h| h.pattern_id != id);
+            if hashes.is_empty() {
+                self.prefix_table.remove(last_block);
+            }
+        }
+
+        // Uncount the shift value of each block of the pattern. The smallest remaining
+        // one is the new shift value of the block.
+        for j in b ..= min_length {
+            let block = &pattern[j-b .. j];
+            let counts = self.shift_counts.get_mut(block).unwrap();
+
+            let count = counts.get_mut(&(min_length - j)).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&(min_length - j));
+            }
+
+            match counts.first_key_value() {
+                Some((shift, _)) => { self.shift_table.insert(block.to_vec(), *shift); },
+                None => {
+                    self.shift_counts.remove(block);
+                    self.shift_table.remove(block);
                 }
             }
         }
+    }
 
-        // Copy patterns to save in the struct.
-        let patterns = patterns.iter().map(|p| p.to_string()).collect();
+    /// Write the compiled tables, so that they can be loaded without recomputation.
+    pub fn serialize(&self, writer: &mut Writer) {
+        writer.write_usize(self.b);
+        writer.write_usize(self.min_length);
+        writer.write_usize(self.default_shift);
 
-        // Return with the compiled struct.
-        return WuManber { patterns, shift_table, prefix_table, min_length, default_shift, b };
+        writer.write_usize(self.patterns.len());
+        for pattern in &self.patterns {
+            writer.write_str(pattern);
+        }
+
+        writer.write_usize(self.shift_table.len());
+        for (block, shift) in &self.shift_table {
+            writer.write_bytes(block);
+            writer.write_usize(*shift);
+        }
+
+        writer.write_usize(self.prefix_table.len());
+        for (block, hashes) in &self.prefix_table {
+            writer.write_bytes(block);
+            writer.write_usize(hashes.len());
+            for hash in hashes {
+                writer.write_usize(hash.pattern_id);
+                writer.write_bytes(&hash.prefix);
+            }
+        }
     }
 
-    /// Finds any one of the compiled patterns in the given text.
-    pub fn find<'t>(&self, text: &'t str) -> Option<(Match<'t>, usize)> {
-        let mut pos = self.min_length;
+    /// Read the tables written by `serialize`, and check their consistency,
+    /// so that searching with them can't fail.
+    pub fn deserialize(reader: &mut Reader) -> Result<WuManber, Error> {
+        let b = reader.read_usize()?;
+        let min_length = reader.read_usize()?;
+        let default_shift = reader.read_usize()?;
 
-        // We loop while there's text to read.
-        while pos <= text.len() {
-            let block = &text[pos-self.b .. pos];
+        if b == 0 || min_length < b || default_shift != min_length + 1 - b {
+            return Err(Error::Format("Invalid Wu-Manber parameters."));
+        }
+
+        let mut patterns = vec![];
+        for _ in 0..reader.read_length()? {
+            let pattern = reader.read_str()?;
+            if pattern.len() < min_length {
+                return Err(Error::Format("Pattern is shorter than the minimum length."));
+            }
+            patterns.push(pattern);
+        }
+
+        let mut shift_table = HashMap::new();
+        for _ in 0..reader.read_length()? {
+            let block = reader.read_bytes()?;
+            let shift = reader.read_usize()?;
+            shift_table.insert(block, shift);
+        }
+
+        let mut prefix_table: HashMap<Vec<u8>, Vec<PrefixHash>> = HashMap::new();
+        for _ in 0..reader.read_length()? {
+            let block = reader.read_bytes()?;
+            let mut hashes = vec![];
+            for _ in 0..reader.read_length()? {
+                let pattern_id = reader.read_usize()?;
+                let prefix = reader.read_bytes()?;
+                if pattern_id >= patterns.len() {
+                    return Err(Error::Format("Invalid pattern reference in the prefix table."));
+                }
+                hashes.push(PrefixHash { pattern_id, prefix });
+            }
+            prefix_table.insert(block, hashes);
+        }
 
-            let shift = self.shift_table.get(block).unwrap_or(&self.default_shift).to_owned();
+        // Blocks with a zero shift value are looked up in the prefix table during searching.
+        for (block, shift) in &shift_table {
+            if *shift == 0 && !prefix_table.contains_key(block) {
+                return Err(Error::Format("Missing prefix table entry."));
+            }
+        }
+
+        // The counts of the shift values are only needed to remove patterns, so they are not
+        // stored, but counted again. The stored shift values must be the smallest ones.
+        let mut matcher = WuManber {
+            patterns: vec![],
+            live: vec![],
+            shift_table: HashMap::new(),
+            shift_counts: HashMap::new(),
+            prefix_table,
+            min_length,
+            default_shift,
+            b,
+            counters: Counters::new(reader.config().stats)
+        };
+        for pattern in &patterns {
+            matcher.count_shifts(pattern.as_bytes());
+        }
+        if shift_table != matcher.shift_table {
+            return Err(Error::Format("Inconsistent shift table."));
+        }
+
+        matcher.live = vec![true; patterns.len()];
+        matcher.patterns = patterns;
+        return Ok(matcher);
+    }
+
+    /// Returns the patterns the struct was compiled with.
+    pub fn patterns(&self) -> &[String] {
+        return &self.patterns;
+    }
+
+    /// Returns the counters of the positions where a whole pattern was compared with the
+    /// text, and of the ones where it was found.
+    pub fn stats(&self) -> Stats {
+        return self.counters.wu_manber_stats();
+    }
+
+    /// Describes the properties of the compiled tables.
+    pub fn explain(&self) -> WuManberExplanation {
+        return WuManberExplanation {
+            block_size: self.b,
+            min_length: self.min_length,
+            default_shift: self.default_shift,
+            shift_table_size: self.shift_table.len(),
+            prefix_table_size: self.prefix_table.len(),
+        };
+    }
+
+    /// Returns an iterator over every occurrence of every compiled pattern in the given text,
+    /// including overlapping ones. Occurrences are ordered by their start positions, and
+    /// occurrences starting at the same position are ordered by their pattern ids.
+    pub fn find_iter<'w, 't>(&'w self, text: &'t str) -> Occurrences<'w, 't> {
+        return Occurrences { matcher: self, text, pos: self.min_length, limit: usize::MAX, pending: vec![] };
+    }
+}
+
+impl Occurrences<'_, '_> {
+    /// Stops the search at the given offset: only the occurrences
+    /// starting at or before it are reported.
+    pub fn until(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        return self;
+    }
+}
+
+impl<'w, 't> Iterator for Occurrences<'w, 't> {
+    type Item = (Match<'t>, usize);
+
+    /// Finds the next occurrence of any one of the compiled patterns.
+    fn next(&mut self) -> Option<(Match<'t>, usize)> {
+        let (matcher, text) = (self.matcher, self.text);
+        let bytes = text.as_bytes();
+
+        // The verified candidates of the last window are returned first. They are
+        // stored in reverse order, so that they can be popped from the end.
+        if let Some(occurrence) = self.pending.pop() {
+            return Some(occurrence);
+        }
+
+        // We loop while there's text to read, and the windows start before the limit.
+        while self.pos <= text.len() && self.pos - matcher.min_length <= self.limit {
+            let pos = self.pos;
+            let block = &bytes[pos-matcher.b .. pos];
+
+            let shift = matcher.shift_table.get(block).unwrap_or(&matcher.default_shift).to_owned();
+            self.pos += max(shift, 1);
 
             // We found a potential match - check through prefixes for matching.
             if shift == 0 {
-                let prefix_start = pos - self.min_length;
-                let prefix = &text[prefix_start .. (prefix_start + self.b)];
+                let prefix_start = pos - matcher.min_length;
+                let prefix = &bytes[prefix_start .. (prefix_start + matcher.b)];
 
-                for candidate in self.prefix_table.get(block).unwrap() {
+                for candidate in matcher.prefix_table.get(block).unwrap().iter().rev() {
                     // If any candidate matches, try comparing the text with the referenced pattern.
                     if candidate.prefix == prefix {
-                        let refd_pattern = &self.patterns[candidate.pattern_id];
+                        let refd_pattern = &matcher.patterns[candidate.pattern_id];
                         let start = prefix_start;
                         let end = start + refd_pattern.len();
 
-                        // If the whole pattern matches, return with a successful match.
-                        if end <= text.len() && refd_pattern == &text[start..end] {
-                            return Some((Match::new(start, end, &text[start..end]), candidate.pattern_id));
+                        // If the whole pattern matches, store the occurrence. A pattern can only be
+                        // equal to the bytes of the text at character boundaries.
+                        let found = end <= text.len() && refd_pattern.as_bytes() == &bytes[start..end];
+                        matcher.counters.record(found);
+
+                        if found {
+                            self.pending.push((Match::new(start, end, &text[start..end]), candidate.pattern_id));
                         }
                     }
                 }
-            }
 
-            pos += max(shift, 1);
+                if let Some(occurrence) = self.pending.pop() {
+                    return Some(occurrence);
+                }
+            }
         }
 
-        // If we reached the end of the text, return with no match.
+        // If we reached the end of the text, there are no more occurrences.
         return None;
     }
 }
@@ -1,83 +1,434 @@
-use regex::Regex;
-
-use crate::{types::Error};
+use crate::{builder::Config, types::{Error, Suggestion}};
 
 /// An object that can be used to preprocess a pattern string and decide
 /// the best matching method to use during the pattern matching.
 pub struct Preprocessor<'p> {
     /// The pattern string itself.
-    pattern: &'p str
+    pattern: &'p str,
+    /// The pattern split into the syntactic elements relevant to the heuristics.
+    tokens: Vec<Token>,
+    /// The options of the compilation, like the byte frequencies used to score the
+    /// literal fragments of the pattern, and the line terminator.
+    config: &'p Config,
 }
 
-/// Contains the various heuristic implementations that can be used for pattern matching.
-#[derive(PartialEq, Clone)]
-pub enum Suggestion {
-    Literal,
-    Longest,
-    Prefix,
-    Nothing,
+/// A single syntactic element of a pattern, as seen by the heuristics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    /// A character that only matches itself.
+    Literal(char),
+    /// A character class (including '.') that matches one character of at most the
+    /// given byte length. Some classes (like `[^a]` or `\s`) may match a line terminator too.
+    Class { max_length: usize, crosses_lines: bool },
+    /// A zero-width assertion, like '^', '$' or '\b'.
+    Anchor,
+    /// A repetition operator. Optional ones ('*', '?') allow the previous item to be absent,
+    /// unbounded ones ('*', '+') allow it to be repeated any number of times, while counted
+    /// ones ('{n,m}') are bounded, but are not handled by the heuristics at all.
+    Repeat { optional: bool, unbounded: bool, counted: bool },
+    /// The start of a group. Flag groups (like `(?i)`) change the meaning of the
+    /// remaining pattern, and prevent the use of any heuristic.
+    GroupOpen { flags: bool },
+    /// The end of a group.
+    GroupClose,
+    /// The alternation operator '|'.
+    Alternation,
 }
 
 impl<'p> Preprocessor<'p> {
-    /// Creates a new instance from the given pattern and flags.
-    pub fn new(pattern: &str) -> Preprocessor {
-        return Preprocessor { pattern };
+    /// Creates a new instance from the given pattern, with the byte frequency table
+    /// and the line terminator of the options.
+    pub fn new(pattern: &'p str, config: &'p Config) -> Preprocessor<'p> {
+        return Preprocessor { pattern, tokens: tokenize(pattern, config.terminator.byte() as char), config };
     }
 
     /// Preprocesses the stored pattern, and determines which heuristic should be used
     /// during text searching. May return an error, if the pattern is not a valid pattern.
     pub fn determine_type(&self) -> Result<Suggestion, Error> {
         // We try to properly compile the pattern first, and see if it succeeds.
-        if Regex::new(self.pattern).is_err() {
+        if self.config.compile(self.pattern).is_err() {
             return Err(Error::Syntax("Preliminary compile check failed."));
         }
 
-        // Inner state variables.
-        let is_multiline = self.pattern.contains('\n') || self.pattern.contains("\\n");
+        // Flag groups may change the meaning of literal characters, so no heuristics can be used.
+        if self.tokens.iter().any(|t| matches!(t, Token::GroupOpen { flags: true })) {
+            return Ok(Suggestion::Nothing);
+        }
 
-        let mut is_literal = true;
-        let mut is_longest = true;
-        let mut is_prefix = true;
-        let mut state_escaped = false;
+        if self.is_literal() {
+            return Ok(Suggestion::Literal);
+        }
 
-        // We go through the pattern char by char.
-        for (i, c) in self.pattern.char_indices() {
-            // A '\' changes the escaped state.
-            if c == '\\' {
-                state_escaped = !state_escaped;
-            }
+        // Score the literal fragment of each applicable heuristic, and choose the rarest one.
+        // On equal scores, the heuristics listed first are preferred.
+        let candidates = [
+            (Suggestion::Longest, self.is_longest(), self.best_fragment()),
+            (Suggestion::Prefix, self.is_prefix(), self.prefix()),
+            (Suggestion::Suffix, self.is_suffix(), self.suffix()),
+        ];
 
-            // We can only use the prefix heuristic if the first two chars aren't special chars.
-            if (i == 0 || i == 1) && ['\\', '.', '[', '^', '$', '*', '+', '?', '(', '|', '{'].contains(&c) {
-                is_prefix = false;
+        let mut best = (Suggestion::Nothing, 0.0);
+        for (suggestion, applicable, fragment) in candidates {
+            let score = self.config.frequencies.score(&fragment);
+            if applicable && !fragment.is_empty() && (best.0 == Suggestion::Nothing || score > best.1) {
+                best = (suggestion, score);
             }
+        }
 
-            // If the character isn't escaped, the presence of the following special characters
-            // might prevent us from using the literal and longest heuristics.
-            if !state_escaped {
-                // Any of the following special characters are illegal in a literal string.
-                if is_literal && ['.', '[', '^', '$', '*', '+', '?', '(', '|', '{'].contains(&c) {
-                    is_literal = false;
-                }
+        return Ok(best.0);
+    }
+
+    /// Determines whether the pattern only matches itself, as a literal string.
+    pub fn is_literal(&self) -> bool {
+        return self.tokens.iter().all(|t| matches!(t, Token::Literal(_)));
+    }
+
+    /// Returns the string matched by the pattern, if it is a literal pattern.
+    pub fn literal(&self) -> String {
+        return self.tokens
+            .iter()
+            .filter_map(|t| if let Token::Literal(c) = t { Some(*c) } else { None })
+            .collect();
+    }
+
+    /// Returns every literal fragment that must be present in a match of the pattern.
+    /// Only meaningful if the longest heuristic can be used with the pattern.
+    pub fn fragments(&self) -> Vec<String> {
+        let mut fragments = vec![];
+        let mut current = String::from("");
+        let mut previous: Option<&Token> = None;
 
-                // Any of the following are illegal in the longest matcher too.
-                if is_longest && ['(', '|', '{'].contains(&c) {
-                    is_longest = false;
+        for token in &self.tokens {
+            if let Token::Literal(c) = token {
+                current.push(*c);
+            } else {
+                // If the modifier makes the previous character optional, pop it from the fragment.
+                if let (Token::Repeat { optional: true, .. }, Some(Token::Literal(_))) = (token, previous) {
+                    current.pop();
                 }
 
-                // These are only illegal if the pattern can cross over line boundaries.
-                if is_longest && is_multiline && ['*', '+', '?'].contains(&c) {
-                    is_longest = false;
+                // With every other token, the current fragment ends.
+                if !current.is_empty() {
+                    fragments.push(current);
+                    current = String::from("");
                 }
             }
+
+            previous = Some(token);
+        }
+
+        if !current.is_empty() {
+            fragments.push(current);
+        }
+
+        return fragments;
+    }
+
+    /// Returns the rarest literal fragment of the pattern, according to the frequency table.
+    pub fn best_fragment(&self) -> String {
+        let mut best = String::from("");
+        let mut best_score = 0.0;
+
+        for fragment in self.fragments() {
+            let score = self.config.frequencies.score(&fragment);
+            if score > best_score {
+                best = fragment;
+                best_score = score;
+            }
+        }
+
+        return best;
+    }
+
+    /// Returns the maximum length (in bytes) a match of the pattern can have, or None,
+    /// if it can't be determined. Only meaningful if the longest heuristic can be used.
+    pub fn max_length(&self) -> Option<usize> {
+        let mut length = 0;
+
+        for token in &self.tokens {
+            match token {
+                Token::Literal(c) => length += c.len_utf8(),
+                Token::Class { max_length, .. } => length += max_length,
+                Token::Repeat { unbounded: true, .. } => return None,
+                _ => ()
+            }
+        }
+
+        return Some(length);
+    }
+
+    /// Returns the literal string every match of the pattern must start with.
+    /// May be empty, if the pattern doesn't start with a literal character.
+    pub fn prefix(&self) -> String {
+        let mut prefix = String::from("");
+
+        for token in &self.tokens {
+            match token {
+                Token::Literal(c) => prefix.push(*c),
+                // An optional or counted repetition (possibly) makes the last character optional.
+                Token::Repeat { optional, counted, .. } => {
+                    if *optional || *counted {
+                        prefix.pop();
+                    }
+                    break;
+                },
+                _ => break
+            }
+        }
+
+        return prefix;
+    }
+
+    /// Returns the literal string every match of the pattern must end with.
+    /// May be empty, if the pattern doesn't end with a literal character.
+    pub fn suffix(&self) -> String {
+        let mut suffix = vec![];
+
+        for token in self.tokens.iter().rev() {
+            match token {
+                Token::Literal(c) => suffix.push(*c),
+                _ => break
+            }
+        }
+
+        return suffix.iter().rev().collect();
+    }
+
+    /// Determines whether the longest heuristic can be used: the pattern must be a simple
+    /// sequence of literals and classes, whose matches can be located around a fragment.
+    fn is_longest(&self) -> bool {
+        let mut crosses_lines = false;
+        let mut unbounded = false;
+
+        for token in &self.tokens {
+            match token {
+                Token::Literal(c) if *c == self.config.terminator.byte() as char => crosses_lines = true,
+                Token::Class { crosses_lines: true, .. } => crosses_lines = true,
+                Token::Repeat { counted: true, .. } => return false,
+                Token::Repeat { unbounded: true, .. } => unbounded = true,
+                Token::Anchor | Token::GroupOpen { .. } | Token::GroupClose | Token::Alternation => return false,
+                _ => ()
+            }
         }
 
-        // Return the correct suggestion.
-        return match () {
-            () if is_literal => Ok(Suggestion::Literal),
-            () if is_longest => Ok(Suggestion::Longest),
-            () if is_prefix => Ok(Suggestion::Prefix),
-            () => Ok(Suggestion::Nothing)
+        // Matches of unknown length are searched for in the line of the fragment,
+        // which only works if a match can't cross over line terminators.
+        return !(crosses_lines && unbounded);
+    }
+
+    /// Determines whether the prefix heuristic can be used: every match must start
+    /// with the same literal string.
+    fn is_prefix(&self) -> bool {
+        return !self.has_top_level_alternation();
+    }
+
+    /// Determines whether the suffix heuristic can be used: every match must end with
+    /// the same literal string, and must be contained in a single line.
+    fn is_suffix(&self) -> bool {
+        let crosses_lines = self.tokens.iter().any(|t| {
+            matches!(t, Token::Class { crosses_lines: true, .. })
+                || *t == Token::Literal(self.config.terminator.byte() as char)
+        });
+        let anchored = self.tokens.contains(&Token::Anchor);
+
+        return !crosses_lines && !anchored && !self.has_top_level_alternation();
+    }
+
+    /// Determines whether the pattern contains an alternation outside of every group.
+    fn has_top_level_alternation(&self) -> bool {
+        let mut depth = 0;
+
+        for token in &self.tokens {
+            match token {
+                Token::GroupOpen { .. } => depth += 1,
+                Token::GroupClose => depth -= 1,
+                Token::Alternation if depth == 0 => return true,
+                _ => ()
+            }
+        }
+
+        return false;
+    }
+}
+
+/// Splits the pattern into tokens, with the classes that may match the given line
+/// terminator marked. Never fails: the parts of an invalid pattern are simply treated
+/// as literals.
+fn tokenize(pattern: &str, terminator: char) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut iter = pattern.chars().peekable();
+
+    while let Some(c) = iter.next() {
+        let token = match c {
+            '\\' => match iter.next() {
+                // Escape sequences with a letter or digit are usually classes or assertions.
+                Some(e) if e.is_ascii_alphanumeric() => parse_escape(e, &mut iter, terminator),
+                // The start and end of a word are assertions too.
+                Some('<') | Some('>') => Token::Anchor,
+                // Escaped special characters are literals.
+                Some(e) => Token::Literal(e),
+                None => Token::Literal('\\'),
+            },
+            '.' => Token::Class { max_length: 4, crosses_lines: false },
+            '[' => parse_class(&mut iter, terminator),
+            '^' | '$' => Token::Anchor,
+            '*' | '+' | '?' => {
+                // Skip the lazy modifier, it doesn't matter to the heuristics.
+                if iter.peek() == Some(&'?') {
+                    iter.next();
+                }
+                Token::Repeat { optional: c != '+', unbounded: c != '?', counted: false }
+            },
+            '{' => {
+                for c in iter.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+                if iter.peek() == Some(&'?') {
+                    iter.next();
+                }
+                Token::Repeat { optional: false, unbounded: false, counted: true }
+            },
+            '(' => {
+                // Groups starting with '?' and a flag character set flags, while
+                // non-capturing and named groups start with '?:', '?P<' or '?<'.
+                let mut lookahead = iter.clone();
+                let flags = lookahead.next() == Some('?')
+                    && !matches!(lookahead.next(), Some(':') | Some('P') | Some('<'));
+                Token::GroupOpen { flags }
+            },
+            ')' => Token::GroupClose,
+            '|' => Token::Alternation,
+            c => Token::Literal(c),
         };
+
+        // A repetition of a repetition (like `a+*`) applies to the same item, so the two are
+        // merged: the item may be absent, or repeated without bounds, if either allows it.
+        if let (Token::Repeat { optional, unbounded, counted }, Some(Token::Repeat { optional: o, unbounded: u, counted: c })) = (token, tokens.last_mut()) {
+            *o |= optional;
+            *u |= unbounded;
+            *c |= counted;
+            continue;
+        }
+
+        tokens.push(token);
     }
+
+    return tokens;
+}
+
+/// Parses an escape sequence starting with the given letter or digit.
+fn parse_escape<I>(escaped: char, iter: &mut std::iter::Peekable<I>, terminator: char) -> Token
+    where I: Iterator<Item = char>
+{
+    return match escaped {
+        'n' => Token::Literal('\n'),
+        't' => Token::Literal('\t'),
+        'r' => Token::Literal('\r'),
+        'B' | 'A' | 'z' => Token::Anchor,
+        // Word boundaries may be followed by their kind, like `\b{start}`.
+        'b' => {
+            if iter.peek() == Some(&'{') {
+                for c in iter.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            Token::Anchor
+        },
+        // These classes only match the terminators that belong to them.
+        'd' => Token::Class { max_length: 4, crosses_lines: terminator.is_ascii_digit() },
+        'w' => Token::Class { max_length: 4, crosses_lines: terminator.is_ascii_alphanumeric() || terminator == '_' },
+        'S' => Token::Class { max_length: 4, crosses_lines: !terminator.is_ascii_whitespace() },
+        'x' => parse_code(iter, 2),
+        'u' => parse_code(iter, 4),
+        'U' => parse_code(iter, 8),
+        // Unicode classes may be followed by a bracketed name.
+        'p' | 'P' => {
+            if iter.peek() == Some(&'{') {
+                for c in iter.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            } else {
+                iter.next();
+            }
+            Token::Class { max_length: 4, crosses_lines: true }
+        },
+        _ => Token::Class { max_length: 4, crosses_lines: true },
+    };
+}
+
+/// Parses the hexadecimal code of a character, after its escape, into the literal character.
+/// The code is either bracketed, or made of exactly the given number of digits.
+fn parse_code<I>(iter: &mut std::iter::Peekable<I>, digits: usize) -> Token
+    where I: Iterator<Item = char>
+{
+    let mut code = String::new();
+
+    if iter.peek() == Some(&'{') {
+        iter.next();
+        for c in iter.by_ref() {
+            if c == '}' {
+                break;
+            }
+            code.push(c);
+        }
+    } else {
+        code.extend(iter.by_ref().take(digits));
+    }
+
+    // Codes the parser rejects later are handled conservatively.
+    return match u32::from_str_radix(code.trim(), 16).ok().and_then(char::from_u32) {
+        Some(c) => Token::Literal(c),
+        None => Token::Class { max_length: 4, crosses_lines: true },
+    };
+}
+
+/// Parses a bracketed character class, after its opening bracket.
+fn parse_class<I>(iter: &mut std::iter::Peekable<I>, terminator: char) -> Token
+    where I: Iterator<Item = char>
+{
+    let negated = iter.peek() == Some(&'^');
+    if negated {
+        iter.next();
+    }
+
+    // A closing bracket right after the opening one is a literal.
+    let mut first = true;
+    let mut depth = 1;
+    let mut ascii_only = true;
+    let mut has_line_break = false;
+
+    while let Some(c) = iter.next() {
+        match c {
+            ']' if !first => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            },
+            '[' => depth += 1,
+            '\\' => {
+                // Escapes other than punctuation (like \d or \n) are handled conservatively.
+                if let Some(e) = iter.next() {
+                    ascii_only &= e.is_ascii_punctuation();
+                    has_line_break |= !e.is_ascii_punctuation();
+                }
+            },
+            c => {
+                ascii_only &= c.is_ascii();
+                has_line_break |= c == terminator;
+            },
+        }
+        first = false;
+    }
+
+    return Token::Class {
+        max_length: if ascii_only && !negated { 1 } else { 4 },
+        crosses_lines: negated || has_line_break,
+    };
 }
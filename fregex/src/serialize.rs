@@ -0,0 +1,231 @@
+use crate::{
+    builder::{Config, LineTerminator},
+    matcher::Matcher,
+    matchers::{BoundedMatcher, LiteralMatcher, LongestMatcher, NothingMatcher, PrefixMatcher, SuffixMatcher},
+    multimatchers::{HybridMultiMatcher, LiteralMultiMatcher, LongestMultiMatcher, NothingMultiMatcher},
+    types::Error
+};
+
+/// The bytes every serialized matcher starts with.
+pub const MAGIC: &[u8; 4] = b"FREX";
+/// The version of the binary format. Data with a different version is rejected.
+pub const VERSION: u16 = 4;
+
+/// Tags identifying the serialized matcher implementations.
+pub const TAG_LITERAL: u8 = 0;
+pub const TAG_LONGEST: u8 = 1;
+pub const TAG_PREFIX: u8 = 2;
+pub const TAG_SUFFIX: u8 = 3;
+pub const TAG_NOTHING: u8 = 4;
+pub const TAG_LITERAL_MULTI: u8 = 5;
+pub const TAG_LONGEST_MULTI: u8 = 6;
+pub const TAG_NOTHING_MULTI: u8 = 7;
+pub const TAG_HYBRID_MULTI: u8 = 8;
+pub const TAG_BOUNDED: u8 = 9;
+
+/// The tags of the matchers of a single pattern, which don't contain other matchers.
+pub const SINGLE_TAGS: &[u8] = &[TAG_LITERAL, TAG_LONGEST, TAG_PREFIX, TAG_SUFFIX, TAG_NOTHING];
+/// The tags of the matchers a serialized `Regex` may consist of.
+pub const REGEX_TAGS: &[u8] = &[TAG_BOUNDED, TAG_LITERAL, TAG_LONGEST, TAG_PREFIX, TAG_SUFFIX, TAG_NOTHING];
+/// The tags of the matchers a serialized `MultiRegex` may consist of. A set of a single
+/// pattern is compiled into the matcher of the pattern.
+pub const MULTI_REGEX_TAGS: &[u8] = &[
+    TAG_BOUNDED, TAG_LITERAL, TAG_LONGEST, TAG_PREFIX, TAG_SUFFIX, TAG_NOTHING,
+    TAG_LITERAL_MULTI, TAG_LONGEST_MULTI, TAG_NOTHING_MULTI, TAG_HYBRID_MULTI,
+];
+
+/// Tags identifying whether a `Regex` or a `MultiRegex` was serialized.
+pub const KIND_SINGLE: u8 = 0;
+pub const KIND_MULTI: u8 = 1;
+
+/// Appends values to a binary buffer in the serialization format.
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+/// Reads values written by a `Writer` from a binary buffer.
+pub struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+    /// The options the matcher was compiled with, read from the header: only the
+    /// line terminator and the multi-line mode are stored.
+    config: Config,
+}
+
+impl Writer {
+    /// Creates a new writer, and writes the format header with the given kind, and the
+    /// line terminator and multi-line mode shared by every pattern of the matcher.
+    pub fn new(kind: u8, config: &Config) -> Writer {
+        let mut writer = Writer { bytes: vec![] };
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.bytes.extend_from_slice(&VERSION.to_le_bytes());
+        writer.write_u8(kind);
+        writer.write_u8(config.terminator.byte());
+        writer.write_bool(config.terminator == LineTerminator::Crlf);
+        writer.write_bool(config.multi_line);
+        return writer;
+    }
+
+    /// Returns the written bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        return self.bytes;
+    }
+
+    /// Writes a single byte.
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    /// Writes a boolean value as a single byte.
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    /// Writes an unsigned number. Always uses 8 bytes, regardless of the platform.
+    pub fn write_usize(&mut self, value: usize) {
+        self.bytes.extend_from_slice(&(value as u64).to_le_bytes());
+    }
+
+    /// Writes a length-prefixed string.
+    pub fn write_str(&mut self, value: &str) {
+        self.write_bytes(value.as_bytes());
+    }
+
+    /// Writes a length-prefixed byte string, in the same format as `write_str`.
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.write_usize(value.len());
+        self.bytes.extend_from_slice(value);
+    }
+}
+
+impl<'b> Reader<'b> {
+    /// Creates a new reader, and checks the format header against the given kind.
+    pub fn new(bytes: &'b [u8], kind: u8) -> Result<Reader<'b>, Error> {
+        let mut reader = Reader { bytes, pos: 0, config: Config::default() };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::Format("Not a serialized matcher."));
+        }
+        if reader.take(2)? != VERSION.to_le_bytes() {
+            return Err(Error::Format("Unsupported serialization format version."));
+        }
+        if reader.read_u8()? != kind {
+            return Err(Error::Format("Serialized matcher is of a different kind."));
+        }
+
+        let byte = reader.read_u8()?;
+        if !byte.is_ascii() {
+            return Err(Error::Format("Line terminator is not an ASCII byte."));
+        }
+        reader.config.terminator = if reader.read_bool()? { LineTerminator::Crlf } else { LineTerminator::Byte(byte) };
+        reader.config.multi_line = reader.read_bool()?;
+
+        return Ok(reader);
+    }
+
+    /// Returns the options the matcher was compiled with. Only the line terminator
+    /// and the multi-line mode differ from the defaults.
+    pub fn config(&self) -> &Config {
+        return &self.config;
+    }
+
+    /// Returns the line terminator the matcher was compiled with.
+    pub fn terminator(&self) -> LineTerminator {
+        return self.config.terminator;
+    }
+
+    /// Checks that every byte of the buffer was read.
+    pub fn finish(&self) -> Result<(), Error> {
+        if self.pos != self.bytes.len() {
+            return Err(Error::Format("Unexpected data after the serialized matcher."));
+        }
+
+        return Ok(());
+    }
+
+    /// Reads the given number of bytes.
+    fn take(&mut self, count: usize) -> Result<&'b [u8], Error> {
+        if self.bytes.len() - self.pos < count {
+            return Err(Error::Format("Unexpected end of serialized data."));
+        }
+
+        let slice = &self.bytes[self.pos .. self.pos + count];
+        self.pos += count;
+        return Ok(slice);
+    }
+
+    /// Reads a single byte.
+    pub fn read_u8(&mut self) -> Result<u8, Error> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    /// Reads a boolean value.
+    pub fn read_bool(&mut self) -> Result<bool, Error> {
+        return match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::Format("Invalid boolean value."))
+        };
+    }
+
+    /// Reads an unsigned number.
+    pub fn read_usize(&mut self) -> Result<usize, Error> {
+        let bytes: [u8; 8] = self.take(8)?.try_into().unwrap();
+        return usize::try_from(u64::from_le_bytes(bytes))
+            .map_err(|_| Error::Format("Number is too large for this platform."));
+    }
+
+    /// Reads a length-prefixed string, and validates its encoding.
+    pub fn read_str(&mut self) -> Result<String, Error> {
+        return String::from_utf8(self.read_bytes()?)
+            .map_err(|_| Error::Format("Invalid UTF-8 string."));
+    }
+
+    /// Reads a length-prefixed byte string, without any validation.
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let length = self.read_usize()?;
+        return Ok(self.take(length)?.to_vec());
+    }
+
+    /// Reads a collection length. Every element takes up at least one byte,
+    /// so lengths larger than the remaining data are rejected early.
+    pub fn read_length(&mut self) -> Result<usize, Error> {
+        let length = self.read_usize()?;
+        if length > self.bytes.len() - self.pos {
+            return Err(Error::Format("Invalid collection length."));
+        }
+
+        return Ok(length);
+    }
+}
+
+/// Compiles the given pattern with the library-supplied matcher and the line
+/// terminator and multi-line mode of the options, to validate a deserialized pattern.
+pub fn compile(pattern: &str, config: &Config) -> Result<regex::Regex, Error> {
+    return config.compile(pattern)
+        .map_err(|_| Error::Syntax("Deserialized pattern failed to compile."));
+}
+
+/// Reads a tagged matcher of one of the given implementations. The matchers containing
+/// other matchers only allow the kinds they are built from, so the nesting depth of the
+/// data is bounded, and crafted data can't exhaust the stack.
+pub fn read_matcher(reader: &mut Reader, tags: &[u8]) -> Result<Box<dyn Matcher>, Error> {
+    let tag = reader.read_u8()?;
+    if !tags.contains(&tag) {
+        return Err(Error::Format("Unexpected matcher type."));
+    }
+
+    return match tag {
+        TAG_LITERAL => Ok(Box::new(LiteralMatcher::deserialize(reader)?)),
+        TAG_LONGEST => Ok(Box::new(LongestMatcher::deserialize(reader)?)),
+        TAG_PREFIX => Ok(Box::new(PrefixMatcher::deserialize(reader)?)),
+        TAG_SUFFIX => Ok(Box::new(SuffixMatcher::deserialize(reader)?)),
+        TAG_NOTHING => Ok(Box::new(NothingMatcher::deserialize(reader)?)),
+        TAG_LITERAL_MULTI => Ok(Box::new(LiteralMultiMatcher::deserialize(reader)?)),
+        TAG_LONGEST_MULTI => Ok(Box::new(LongestMultiMatcher::deserialize(reader)?)),
+        TAG_NOTHING_MULTI => Ok(Box::new(NothingMultiMatcher::deserialize(reader)?)),
+        TAG_HYBRID_MULTI => Ok(Box::new(HybridMultiMatcher::deserialize(reader)?)),
+        TAG_BOUNDED => Ok(Box::new(BoundedMatcher::deserialize(reader, tags)?)),
+        _ => Err(Error::Format("Unknown matcher type."))
+    };
+}
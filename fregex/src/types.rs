@@ -1,3 +1,5 @@
+use std::{fmt, ops};
+
 /// Represents a pattern match on a given text.
 /// 
 /// The lifetime parameter `'t` refers to the lifetime of the matched text.
@@ -11,11 +13,115 @@ pub struct Match<'t> {
     matched_text: &'t str
 }
 
+/// The set of patterns of a `MultiRegex` that match a given text, indexed by pattern id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetMatches {
+    /// Whether the pattern with each id matched. Removed patterns never match.
+    matched: Vec<bool>,
+}
+
+/// Contains the various heuristic implementations that can be used for pattern matching.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Suggestion {
+    /// The pattern contains no special characters, and can be searched for as a string.
+    Literal,
+    /// The longest literal fragment of the pattern is searched for first, and the full
+    /// pattern is only matched in the vicinity of the fragment.
+    Longest,
+    /// The literal prefix of the pattern is searched for first, and the full pattern
+    /// is only matched from the start of the prefix.
+    Prefix,
+    /// The literal suffix of the pattern is searched for first, and the full pattern
+    /// is only matched in the line of the suffix.
+    Suffix,
+    /// No heuristic can be used, the pattern is matched as is.
+    Nothing,
+}
+
+/// The matcher implementation chosen for a compiled pattern or set of patterns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// A single pattern, matched with the heuristic of the given suggestion.
+    Single(Suggestion),
+    /// Multiple literal patterns, matched using the Wu-Manber algorithm.
+    LiteralMulti,
+    /// Multiple patterns whose longest fragments are located using the Wu-Manber algorithm.
+    LongestMulti,
+    /// Multiple patterns without usable literal fragments, compiled into a single automaton
+    /// (the ones with a literal prefix share a Wu-Manber prefilter instead).
+    NothingMulti,
+    /// Multiple patterns, where the literal and longest ones are located using the
+    /// Wu-Manber algorithm, and the rest are matched as with `NothingMulti`.
+    Hybrid,
+}
+
+/// A structured description of how a compiled `Regex` or `MultiRegex` searches for matches.
+#[derive(Clone, Debug)]
+pub struct Explanation {
+    /// The matcher implementation that is used during searching.
+    pub strategy: Strategy,
+    /// The description of each compiled pattern, in the order they were supplied.
+    pub patterns: Vec<PatternExplanation>,
+    /// The properties of the Wu-Manber matcher, if the strategy uses one.
+    pub wu_manber: Option<WuManberExplanation>,
+}
+
+/// A structured description of how a single pattern is matched.
+#[derive(Clone, Debug)]
+pub struct PatternExplanation {
+    /// The original pattern string.
+    pub pattern: String,
+    /// The heuristic chosen for the pattern by the preprocessor.
+    pub suggestion: Suggestion,
+    /// The literal fragments searched for before running the full pattern, if any.
+    pub literals: Vec<String>,
+    /// The minimum length (in bytes) a match of the pattern can have.
+    pub min_length: usize,
+    /// The maximum length a match of the pattern can have, if it can be determined.
+    pub max_length: Option<usize>,
+}
+
+/// A structured description of a compiled Wu-Manber matcher.
+#[derive(Clone, Debug)]
+pub struct WuManberExplanation {
+    /// The size of the blocks used for indexing the shift table.
+    pub block_size: usize,
+    /// The length of the shortest string searched for.
+    pub min_length: usize,
+    /// The shift value used for blocks that do not appear in any of the strings.
+    pub default_shift: usize,
+    /// The number of distinct blocks stored in the shift table.
+    pub shift_table_size: usize,
+    /// The number of distinct blocks stored in the prefix table.
+    pub prefix_table_size: usize,
+}
+
+/// Counters of the work done by a compiled matcher, across every search since it was
+/// compiled. The candidates of the literal prefilters are counted separately from the
+/// ones of the Wu-Manber algorithm, as the latter may be the candidates of the former.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// The number of candidates found by the literal prefilters (the best fragments,
+    /// prefixes or suffixes of the patterns), which had to be verified with the full patterns.
+    pub candidates: u64,
+    /// The number of candidates confirmed as matches by the full patterns.
+    pub confirmed: u64,
+    /// The number of positions where the Wu-Manber algorithm found a block and the prefix
+    /// of a searched string, so the whole string had to be compared with the text.
+    pub wu_manber_candidates: u64,
+    /// The number of Wu-Manber candidates where the whole string was present.
+    pub wu_manber_confirmed: u64,
+}
+
 /// Contains the various error types the application can produce.
 #[derive(Clone, Debug)]
 pub enum Error {
     /// Syntax error: the referenced string could not be parsed as a valid regex pattern.
-    Syntax(&'static str)
+    Syntax(&'static str),
+    /// Format error: the supplied data is not a valid serialized matcher.
+    Format(&'static str),
+    /// Empty error: a multi-pattern matcher was created without any patterns.
+    Empty,
 }
 
 impl<'t> Match<'t> {
@@ -24,6 +130,12 @@ impl<'t> Match<'t> {
         return Match { start, end, matched_text }
     }
 
+    /// Returns the same match, with its offsets moved forward by the given amount.
+    /// Used when a match was found in a slice that starts at the given offset.
+    pub(crate) fn offset(&self, offset: usize) -> Self {
+        return Match::new(self.start + offset, self.end + offset, self.matched_text);
+    }
+
     /// Returns the starting byte offset of the match (inclusive).
     pub fn start(&self) -> usize {
         return self.start;
@@ -39,3 +151,74 @@ impl<'t> Match<'t> {
         return self.matched_text;
     }
 }
+
+impl SetMatches {
+    /// Creates a new instance from the match flags of each pattern id.
+    pub(crate) fn new(matched: Vec<bool>) -> Self {
+        return SetMatches { matched };
+    }
+
+    /// Determines whether any pattern matched.
+    pub fn matched_any(&self) -> bool {
+        return self.matched.contains(&true);
+    }
+
+    /// Determines whether the pattern with the given id matched.
+    pub fn matched(&self, id: usize) -> bool {
+        return self.matched.get(id).copied().unwrap_or(false);
+    }
+
+    /// Returns the ids of the matching patterns in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        return self.matched.iter().enumerate().filter(|(_, m)| **m).map(|(id, _)| id);
+    }
+}
+
+impl ops::Add for Stats {
+    type Output = Stats;
+
+    /// Sums the counters of two matchers.
+    fn add(self, other: Stats) -> Stats {
+        return Stats {
+            candidates: self.candidates + other.candidates,
+            confirmed: self.confirmed + other.confirmed,
+            wu_manber_candidates: self.wu_manber_candidates + other.wu_manber_candidates,
+            wu_manber_confirmed: self.wu_manber_confirmed + other.wu_manber_confirmed,
+        };
+    }
+}
+
+impl fmt::Display for Stats {
+    /// Formats the counters as a human-readable, multi-line description.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "candidates: {}, confirmed: {}", self.candidates, self.confirmed)?;
+        return writeln!(f, "wu-manber candidates: {}, confirmed: {}", self.wu_manber_candidates, self.wu_manber_confirmed);
+    }
+}
+
+impl fmt::Display for Explanation {
+    /// Formats the explanation as a human-readable, multi-line description.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "strategy: {:?}", self.strategy)?;
+
+        if let Some(wm) = &self.wu_manber {
+            writeln!(
+                f, "wu-manber: block size {}, min length {}, default shift {}, shift table size {}, prefix table size {}",
+                wm.block_size, wm.min_length, wm.default_shift, wm.shift_table_size, wm.prefix_table_size
+            )?;
+        }
+
+        for (i, pattern) in self.patterns.iter().enumerate() {
+            let max_length = match pattern.max_length {
+                Some(length) => length.to_string(),
+                None => String::from("unknown")
+            };
+
+            writeln!(f, "pattern {}: {:?} ({:?})", i, pattern.pattern, pattern.suggestion)?;
+            writeln!(f, "  literals: {:?}", pattern.literals)?;
+            writeln!(f, "  length: min {}, max {}", pattern.min_length, max_length)?;
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,119 @@
+use fregex::{MultiRegex, Regex, RegexBuilder, RegexMatcher, types::{Strategy, Suggestion}};
+
+const WORD_INPUTS: &'static [(&str, &str, Option<(usize, usize)>)] = &[
+    ("cat", "concatenate the cat", Some((16, 19))),
+    ("cat", "cat", Some((0, 3))),
+    ("cat", "cats and bobcats", None),
+    ("cat", "a cat_ or a cat.", Some((12, 15))),
+    ("-v", "x-v -v", Some((4, 6))),
+    ("ne.t", "nextnet next", Some((8, 12))),
+    ("foo|foobar", "foobar", Some((0, 6))),
+    ("ab+", "abbc ab", Some((5, 7))),
+    ("(x|y)+z", "xyzw yxz", Some((5, 8))),
+    ("né", "bené né", Some((6, 9))),
+];
+
+const LINE_INPUTS: &'static [(&str, &str, Option<(usize, usize)>)] = &[
+    ("cat", "the cat\ncat\n", Some((8, 11))),
+    ("cat", "cats\ncat", Some((5, 8))),
+    ("cat", "cats\nbobcat", None),
+    ("c.t", "a cut\ncot\n", Some((6, 9))),
+    ("[a-z]+", "two words\noneword\n", Some((10, 17))),
+    ("a|ab", "abc\nab", Some((4, 6))),
+];
+
+/// Returns the coordinates of the first match of the matcher in the text.
+fn coordinates(matcher: &dyn RegexMatcher, text: &str) -> Option<(usize, usize)> {
+    return matcher.find(text).map(|m| (m.start(), m.end()));
+}
+
+/// Test that only whole words are matched, for every kind of pattern.
+#[test]
+fn test_whole_word() {
+    let mut builder = RegexBuilder::new();
+    builder.whole_word(true);
+
+    for (pattern, text, expected) in WORD_INPUTS {
+        let regex = builder.build(pattern).unwrap();
+        assert_eq!(*expected, coordinates(&regex, text), "Unexpected match for {:?}", pattern);
+    }
+}
+
+/// Test that only whole lines are matched, for every kind of pattern.
+#[test]
+fn test_whole_line() {
+    let mut builder = RegexBuilder::new();
+    builder.whole_line(true).whole_word(true);
+
+    for (pattern, text, expected) in LINE_INPUTS {
+        let regex = builder.build(pattern).unwrap();
+        assert_eq!(*expected, coordinates(&regex, text), "Unexpected match for {:?}", pattern);
+    }
+}
+
+/// Test that the boundaries don't change the strategy, so literal patterns keep their prefilters.
+#[test]
+fn test_strategy_is_kept() {
+    let mut builder = RegexBuilder::new();
+    builder.whole_word(true);
+
+    let regex = builder.build("cat").unwrap();
+    assert_eq!(Strategy::Single(Suggestion::Literal), regex.explain().strategy);
+
+    let patterns = ["cat", "dog", "bird"];
+    let regex = builder.build_multi(&patterns).unwrap();
+    assert_eq!(Strategy::LiteralMulti, regex.explain().strategy);
+}
+
+/// Test that the boundaries are checked for every pattern of a set.
+#[test]
+fn test_multi_pattern() {
+    let patterns = ["cat", "dog", "(bird|fish)"];
+    let text = "bobcat dogs fish catdog dog cat";
+
+    let mut builder = RegexBuilder::new();
+    builder.whole_word(true);
+    let mut regex = builder.build_multi(&patterns).unwrap();
+
+    assert_eq!(Some((12, 16)), coordinates(&regex, text));
+    assert_eq!(vec![0, 1, 2], regex.matches(text).iter().collect::<Vec<usize>>());
+
+    let overlapping: Vec<(usize, usize, usize)> = regex.find_overlapping_iter(text).map(|(m, id)| (m.start(), m.end(), id)).collect();
+    assert_eq!(vec![(12, 16, 2), (24, 27, 1), (28, 31, 0)], overlapping);
+
+    // Added patterns are checked for the boundaries too.
+    let id = regex.add("bobcat").unwrap();
+    assert_eq!(Some((0, 6)), coordinates(&regex, text));
+    regex.remove(id);
+    regex.add("dogs?").unwrap();
+    assert_eq!(Some((7, 11)), coordinates(&regex, text));
+
+    // Whole lines can be matched as well.
+    let mut builder = RegexBuilder::new();
+    builder.whole_line(true);
+    let regex = builder.build_multi(&patterns).unwrap();
+
+    assert_eq!(Some((10, 14)), coordinates(&regex, "cat dog\nc\nfish\ndog"));
+}
+
+/// Test that the boundaries are kept when a matcher is serialized.
+#[test]
+fn test_serialization() {
+    let patterns = ["cat", "dog"];
+    let text = "catdog cat";
+
+    let mut builder = RegexBuilder::new();
+    builder.whole_word(true);
+    let regex = builder.build_multi(&patterns).unwrap();
+
+    let mut loaded = MultiRegex::from_bytes(&regex.to_bytes()).unwrap();
+    assert_eq!(Some((7, 10)), coordinates(&loaded, text));
+
+    // Patterns added after loading keep the boundaries of the set.
+    loaded.add("catdo").unwrap();
+    assert_eq!(Some((7, 10)), coordinates(&loaded, text));
+
+    let single = builder.build("dog").unwrap();
+    let loaded = Regex::from_bytes(&single.to_bytes()).unwrap();
+    assert_eq!(None, coordinates(&loaded, text));
+}
@@ -0,0 +1,95 @@
+use std::slice;
+
+use fregex::{Regex, MultiRegex, RegexMatcher, types::{Strategy, Suggestion}};
+
+const SINGLE_INPUTS: &'static [(&str, Suggestion, &[&str], usize, Option<usize>)] = &[
+    ("pattern", Suggestion::Literal, &["pattern"], 7, Some(7)),
+    ("p..ce", Suggestion::Longest, &["ce"], 5, Some(11)),
+    ("[ai][cx]e", Suggestion::Longest, &["e"], 3, Some(3)),
+    ("C[a-z]*a", Suggestion::Longest, &["C"], 2, None),
+    ("x[0-9]+y", Suggestion::Longest, &["x"], 3, None),
+    ("ba(se)+", Suggestion::Prefix, &["ba"], 4, None),
+    ("(text)? x", Suggestion::Suffix, &[" x"], 2, None),
+    ("(text)? x+", Suggestion::Nothing, &[], 2, None),
+];
+
+const MULTI_INPUTS: &'static [(&[&str], Strategy, Option<usize>)] = &[
+    (&["alpha", "beta"], Strategy::LiteralMulti, Some(2)),
+    (&["al?pha+", "pattern"], Strategy::LongestMulti, Some(2)),
+    (&["pattern", "not longest (text)?"], Strategy::Hybrid, Some(2)),
+    (&["(not)? longest", "pattern", "a"], Strategy::Hybrid, Some(2)),
+    (&["x(a|b)", "not longest (text)?"], Strategy::NothingMulti, Some(2)),
+    (&["(not)? longest", "(a|b)c"], Strategy::NothingMulti, None),
+];
+
+const SUGGESTION_INPUTS: &'static [(&[&str], Strategy, &[Suggestion])] = &[
+    (&["foo", "b.r"], Strategy::Hybrid, &[Suggestion::Literal, Suggestion::Longest]),
+    (&["foo", "al[a-z]+a"], Strategy::LongestMulti, &[Suggestion::Literal, Suggestion::Longest]),
+    (&["alpha", "beta"], Strategy::LiteralMulti, &[Suggestion::Literal, Suggestion::Literal]),
+    (&["pattern", "ba(se)+", "(text)? x+"], Strategy::Hybrid, &[Suggestion::Literal, Suggestion::Prefix, Suggestion::Nothing]),
+];
+
+/// Test that the explanation of a single pattern contains the chosen heuristic,
+/// the extracted literals and the minimum and maximum match lengths.
+#[test]
+fn test_explain_single() {
+    for (pattern, suggestion, literals, min_length, max_length) in SINGLE_INPUTS {
+        let explanation = Regex::new(pattern).unwrap().explain();
+
+        assert_eq!(Strategy::Single(*suggestion), explanation.strategy, "Wrong strategy for {:?}", pattern);
+        assert_eq!(1, explanation.patterns.len(), "Expected one pattern for {:?}", pattern);
+        assert!(explanation.wu_manber.is_none(), "Expected no Wu-Manber matcher for {:?}", pattern);
+
+        let details = &explanation.patterns[0];
+        assert_eq!(*pattern, details.pattern);
+        assert_eq!(*suggestion, details.suggestion, "Wrong suggestion for {:?}", pattern);
+        assert_eq!(*literals, details.literals, "Wrong literals for {:?}", pattern);
+        assert_eq!(*min_length, details.min_length, "Wrong min length for {:?}", pattern);
+        assert_eq!(*max_length, details.max_length, "Wrong max length for {:?}", pattern);
+    }
+}
+
+/// Test that the explanation of a single pattern is the same, regardless of
+/// whether the single or the multi-pattern matcher was used.
+#[test]
+fn test_explain_single_multi() {
+    for (pattern, suggestion, _, _, _) in SINGLE_INPUTS {
+        let explanation = MultiRegex::new(slice::from_ref(pattern)).unwrap().explain();
+
+        assert_eq!(Strategy::Single(*suggestion), explanation.strategy, "Wrong strategy for {:?}", pattern);
+    }
+}
+
+/// Test that the explanation of multiple patterns contains the chosen strategy,
+/// every pattern and the Wu-Manber block size (when one is used).
+#[test]
+fn test_explain_multi() {
+    for (patterns, strategy, block_size) in MULTI_INPUTS {
+        let explanation = MultiRegex::new(patterns).unwrap().explain();
+
+        assert_eq!(*strategy, explanation.strategy, "Wrong strategy for {:?}", patterns);
+        assert_eq!(patterns.len(), explanation.patterns.len(), "Wrong pattern count for {:?}", patterns);
+        assert_eq!(*block_size, explanation.wu_manber.map(|wm| wm.block_size), "Wrong block size for {:?}", patterns);
+
+        for (pattern, details) in patterns.iter().zip(explanation.patterns) {
+            assert_eq!(*pattern, details.pattern);
+        }
+    }
+}
+
+/// Test that the explanation of each pattern contains the heuristic chosen by the preprocessor,
+/// regardless of the matcher the pattern is searched for with, even after loading it.
+#[test]
+fn test_explain_suggestions() {
+    for (patterns, strategy, suggestions) in SUGGESTION_INPUTS {
+        let regex = MultiRegex::new(patterns).unwrap();
+        let loaded = MultiRegex::from_bytes(&regex.to_bytes()).unwrap();
+
+        for explanation in [regex.explain(), loaded.explain()] {
+            let found: Vec<Suggestion> = explanation.patterns.iter().map(|p| p.suggestion).collect();
+
+            assert_eq!(*strategy, explanation.strategy, "Wrong strategy for {:?}", patterns);
+            assert_eq!(suggestions.to_vec(), found, "Wrong suggestions for {:?}", patterns);
+        }
+    }
+}
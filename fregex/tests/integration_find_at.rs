@@ -0,0 +1,79 @@
+use fregex::{MultiRegex, Regex, RegexBuilder, RegexMatcher};
+
+const SINGLE_INPUTS: &'static [(&str, &str, usize, Option<(usize, usize)>)] = &[
+    ("pattern", "pattern and pattern", 0, Some((0, 7))),
+    ("pattern", "pattern and pattern", 1, Some((12, 19))),
+    ("pattern", "pattern and pattern", 13, None),
+    ("p..ce", "piece peace", 5, Some((6, 11))),
+    ("^abc", "abc abc", 0, Some((0, 3))),
+    ("^abc", "abc abc", 4, None),
+    ("abc$", "abc abc", 0, Some((4, 7))),
+    ("é+", "aé éé", 3, Some((4, 8))),
+    ("", "text", 2, Some((2, 2))),
+    ("", "text", 4, Some((4, 4))),
+];
+
+const MULTI_INPUTS: &'static [(&[&str], &str, usize, Option<(usize, usize, usize)>)] = &[
+    (&["alpha", "beta"], "alpha beta alpha", 1, Some((6, 10, 1))),
+    (&["alpha", "beta"], "alpha beta alpha", 7, Some((11, 16, 0))),
+    (&["al?pha+", "be(ta)?"], "alpha beta", 2, Some((6, 10, 1))),
+    (&["^alpha", "beta"], "alpha alpha", 1, None),
+    (&["^alpha", "beta"], "alpha beta", 0, Some((0, 5, 0))),
+];
+
+const WORD_INPUTS: &'static [(&str, &str, usize, Option<(usize, usize)>)] = &[
+    ("word", "swords word", 1, Some((7, 11))),
+    ("word", "swords word", 8, None),
+    ("wor.", "words word", 0, Some((6, 10))),
+];
+
+/// Test that a single pattern is searched from the offset, with the text before it
+/// still seen by the anchors.
+#[test]
+fn test_single_find_at() {
+    for (pattern, text, start, expected) in SINGLE_INPUTS {
+        let regex = Regex::new(pattern).unwrap();
+
+        let found = regex.find_at(text, *start).map(|m| (m.start(), m.end()));
+        assert_eq!(*expected, found, "Unexpected match of {:?} in {:?} from {}", pattern, text, start);
+
+        let found = regex.find_with_id_at(text, *start).map(|(m, id)| (m.start(), m.end(), id));
+        assert_eq!(expected.map(|(s, e)| (s, e, 0)), found, "Unexpected match with id of {:?} from {}", pattern, start);
+    }
+}
+
+/// Test that multiple patterns are searched from the offset, with the ids of the matching patterns.
+#[test]
+fn test_multi_find_at() {
+    for (patterns, text, start, expected) in MULTI_INPUTS {
+        let regex = MultiRegex::new(patterns).unwrap();
+
+        let found = regex.find_with_id_at(text, *start).map(|(m, id)| (m.start(), m.end(), id));
+        assert_eq!(*expected, found, "Unexpected match of {:?} in {:?} from {}", patterns, text, start);
+
+        let found = regex.find_at(text, *start).map(|m| (m.start(), m.end()));
+        assert_eq!(expected.map(|(s, e, _)| (s, e)), found, "Unexpected match of {:?} from {}", patterns, start);
+    }
+}
+
+/// Test that the word boundaries are decided by the characters before the offset too.
+#[test]
+fn test_whole_word_find_at() {
+    for (pattern, text, start, expected) in WORD_INPUTS {
+        let regex = RegexBuilder::new().whole_word(true).build(pattern).unwrap();
+
+        let found = regex.find_at(text, *start).map(|m| (m.start(), m.end()));
+        assert_eq!(*expected, found, "Unexpected match of {:?} in {:?} from {}", pattern, text, start);
+    }
+}
+
+/// Test that searching from the start is the same as searching the whole text.
+#[test]
+fn test_find_at_start() {
+    for (pattern, text, _, _) in SINGLE_INPUTS {
+        let regex = Regex::new(pattern).unwrap();
+        let whole = regex.find(text).map(|m| (m.start(), m.end()));
+        let found = regex.find_at(text, 0).map(|m| (m.start(), m.end()));
+        assert_eq!(whole, found, "Unexpected match of {:?} in {:?}", pattern, text);
+    }
+}
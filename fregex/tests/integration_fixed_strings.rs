@@ -0,0 +1,63 @@
+use fregex::{RegexBuilder, RegexMatcher, types::{Strategy, Suggestion}};
+
+const INPUTS: &'static [(&[&str], &str, Option<(usize, usize)>)] = &[
+    (&["a.c"], "abc a.c", Some((4, 7))),
+    (&["(x|y)+"], "xy (x|y)+", Some((3, 9))),
+    (&["[0-9]", "a*"], "aaa a* [0-9]", Some((4, 6))),
+    (&["$HOME", "^", "\\d"], "cd $HOME", Some((3, 8))),
+    (&["not", "present"], "alpha beta", None),
+    (&["x", "??"], "a ?? x", Some((2, 4))),
+    (&["(", "[", "{"], "fn {", Some((3, 4))),
+];
+
+/// Returns the coordinates of the first match of the matcher in the text.
+fn coordinates(matcher: &dyn RegexMatcher, text: &str) -> Option<(usize, usize)> {
+    return matcher.find(text).map(|m| (m.start(), m.end()));
+}
+
+/// Test that fixed strings only match themselves, even with special characters.
+#[test]
+fn test_fixed_strings() {
+    let mut builder = RegexBuilder::new();
+    builder.fixed_strings(true);
+
+    for (patterns, text, expected) in INPUTS {
+        let actual = if patterns.len() == 1 {
+            coordinates(&builder.build(patterns[0]).unwrap(), text)
+        } else {
+            coordinates(&builder.build_multi(patterns).unwrap(), text)
+        };
+        assert_eq!(*expected, actual, "Unexpected match for {:?}", patterns);
+    }
+}
+
+/// Test that invalid regular expressions are accepted as fixed strings.
+#[test]
+fn test_invalid_patterns() {
+    let mut builder = RegexBuilder::new();
+    builder.fixed_strings(true);
+
+    let regex = builder.build("a(b").unwrap();
+    assert_eq!(Strategy::Single(Suggestion::Literal), regex.explain().strategy);
+    assert_eq!(Some((2, 5)), coordinates(&regex, "a a(b"));
+
+    let patterns = ["a(b", "c[d", "e{2"];
+    let regex = builder.build_multi(&patterns).unwrap();
+    assert_eq!(Strategy::LiteralMulti, regex.explain().strategy);
+    assert_eq!(Some((3, 6)), coordinates(&regex, "ab c[d e{2"));
+}
+
+/// Test that added patterns are fixed strings too, and that the boundaries are checked.
+#[test]
+fn test_options() {
+    let mut builder = RegexBuilder::new();
+    builder.fixed_strings(true).whole_word(true);
+
+    let patterns = ["a.b", "c+"];
+    let mut regex = builder.build_multi(&patterns).unwrap();
+    assert_eq!(Some((9, 12)), coordinates(&regex, "xa.b ccc a.b"));
+
+    regex.add("x.").unwrap();
+    assert_eq!(None, coordinates(&regex, "xa ccc"));
+    assert_eq!(Some((3, 5)), coordinates(&regex, "xa x. ccc"));
+}
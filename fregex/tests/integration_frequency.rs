@@ -0,0 +1,45 @@
+use fregex::{RegexBuilder, RegexMatcher, frequency::FrequencyTable, types::{Strategy, Suggestion}};
+
+const INPUTS: &'static [(&str, Suggestion, &str)] = &[
+    ("eeee.#!", Suggestion::Longest, "#!"),
+    ("the[0-9]+Q", Suggestion::Longest, "Q"),
+    ("zq(a|b) the", Suggestion::Prefix, "zq"),
+    ("(a|b)the zq", Suggestion::Suffix, "the zq"),
+];
+
+/// Test that with the built-in frequency table, the rarest literal fragment is chosen,
+/// and that the heuristic is chosen based on the rarity of its fragment.
+#[test]
+fn test_default_frequencies() {
+    for (pattern, suggestion, literal) in INPUTS {
+        let explanation = RegexBuilder::new().build(pattern).unwrap().explain();
+
+        assert_eq!(Strategy::Single(*suggestion), explanation.strategy, "Wrong strategy for {:?}", pattern);
+        assert_eq!(vec![literal.to_string()], explanation.patterns[0].literals, "Wrong literal for {:?}", pattern);
+    }
+}
+
+/// Test that a frequency table built from a corpus overrides the built-in one.
+#[test]
+fn test_corpus_frequencies() {
+    let corpus = "#!#!#!#! #!#!#! #!#! shebangs everywhere #!#!#!";
+    let mut builder = RegexBuilder::new();
+    builder.frequencies(FrequencyTable::from_corpus(corpus.as_bytes()));
+
+    let regex = builder.build("eeee.#!").unwrap();
+    assert_eq!(vec!["eeee".to_string()], regex.explain().patterns[0].literals);
+
+    let text = "#! #! eeeex#!";
+    let actual = regex.find(text).map(|m| (m.start(), m.end()));
+    assert_eq!(Some((6, 13)), actual);
+}
+
+/// Test that fragments are scored by rarity, not by length.
+#[test]
+fn test_score_ordering() {
+    let table = FrequencyTable::default();
+
+    assert!(table.score("#!") > table.score("eeee"));
+    assert!(table.score("the") < table.score("thez"));
+    assert_eq!(0.0, table.score(""));
+}
@@ -0,0 +1,91 @@
+use fregex::{MultiRegex, Regex, RegexBuilder, RegexMatcher};
+
+/// The terminators of the inputs: a NUL byte, a line feed that may be preceded by a
+/// carriage return, or the default line feed.
+#[derive(Clone, Copy, Debug)]
+enum Terminator {
+    Nul,
+    Crlf,
+    Default,
+}
+
+const SINGLE_INPUTS: &'static [(&str, Terminator, bool, &str, Option<(usize, usize)>)] = &[
+    ("a.*b", Terminator::Nul, false, "a\0b a\nb", Some((4, 7))),
+    ("a.*b", Terminator::Default, false, "a\nb a\0b", Some((4, 7))),
+    ("x[a-z ]+ing", Terminator::Nul, false, "xa\0bing x\nsing", None),
+    ("x[a-z\n]+ing", Terminator::Nul, false, "xa\0bing x\nsing", Some((8, 14))),
+    ("fo+ bar", Terminator::Nul, false, "foo\0bar foo bar", Some((8, 15))),
+    ("abc", Terminator::Nul, true, "xabc\0abc\0", Some((5, 8))),
+    ("abc", Terminator::Nul, true, "abc\nabc", None),
+    ("abc", Terminator::Crlf, true, "xabc\r\nabc\r\n", Some((6, 9))),
+    ("abc", Terminator::Default, true, "xabc\r\nabc\r\n", None),
+    ("foo", Terminator::Crlf, true, "a\rfoo\r\nfoo\rb\r\n", Some((2, 5))),
+    ("b", Terminator::Crlf, true, "ab\rb\r\n", Some((3, 4))),
+    ("b", Terminator::Crlf, true, "ab\r\nxb\r\n", None),
+    ("a.b", Terminator::Crlf, false, "a\rb a-b", Some((4, 7))),
+    ("(?m)^b", Terminator::Nul, false, "a\nb\0b", Some((4, 5))),
+];
+
+const MULTI_INPUTS: &'static [(&[&str], Terminator, bool, &str, Option<(usize, usize, usize)>)] = &[
+    (&["alpha.*omega", "beta.*gamma"], Terminator::Nul, false, "alpha\0omega beta\ngamma", Some((12, 22, 1))),
+    (&["alpha.*omega", "be?ta+"], Terminator::Nul, false, "alpha\0omega btaaa", Some((12, 17, 1))),
+    (&["alpha", "beta"], Terminator::Nul, true, "alpha beta\0beta\0", Some((11, 15, 1))),
+    (&["alpha", "beta"], Terminator::Crlf, true, "alpha beta\r\nbeta\r\n", Some((12, 16, 1))),
+    (&["alpha", "beta"], Terminator::Crlf, true, "alpha beta\rbeta\r\n", Some((11, 15, 1))),
+];
+
+/// Creates a builder with the given terminator, matching whole lines if requested.
+fn builder(terminator: Terminator, whole_line: bool) -> RegexBuilder {
+    let mut builder = RegexBuilder::new();
+    builder.whole_line(whole_line);
+
+    match terminator {
+        Terminator::Nul => { builder.line_terminator(b'\0'); },
+        Terminator::Crlf => { builder.crlf(true); },
+        Terminator::Default => ()
+    }
+
+    return builder;
+}
+
+/// Test that the matches of a single pattern are confined to the records of the terminator.
+#[test]
+fn test_single_line_terminator() {
+    for (pattern, terminator, whole_line, text, expected) in SINGLE_INPUTS {
+        let regex = builder(*terminator, *whole_line).build(pattern).unwrap();
+
+        let found = regex.find(text).map(|m| (m.start(), m.end()));
+        assert_eq!(*expected, found, "Unexpected match of {:?} in {:?} with {:?}", pattern, text, terminator);
+    }
+}
+
+/// Test that the matches of multiple patterns are confined to the records of the terminator.
+#[test]
+fn test_multi_line_terminator() {
+    for (patterns, terminator, whole_line, text, expected) in MULTI_INPUTS {
+        let regex = builder(*terminator, *whole_line).build_multi(patterns).unwrap();
+
+        let found = regex.find_with_id(text).map(|(m, id)| (m.start(), m.end(), id));
+        assert_eq!(*expected, found, "Unexpected match of {:?} in {:?} with {:?}", patterns, text, terminator);
+    }
+}
+
+/// Test that the terminator is kept by the serialized matchers.
+#[test]
+fn test_serialized_line_terminator() {
+    for (pattern, terminator, whole_line, text, expected) in SINGLE_INPUTS {
+        let bytes = builder(*terminator, *whole_line).build(pattern).unwrap().to_bytes();
+        let regex = Regex::from_bytes(&bytes).unwrap();
+
+        let found = regex.find(text).map(|m| (m.start(), m.end()));
+        assert_eq!(*expected, found, "Unexpected match of loaded {:?} with {:?}", pattern, terminator);
+    }
+
+    for (patterns, terminator, whole_line, text, expected) in MULTI_INPUTS {
+        let bytes = builder(*terminator, *whole_line).build_multi(patterns).unwrap().to_bytes();
+        let regex = MultiRegex::from_bytes(&bytes).unwrap();
+
+        let found = regex.find_with_id(text).map(|(m, id)| (m.start(), m.end(), id));
+        assert_eq!(*expected, found, "Unexpected match of loaded {:?} with {:?}", patterns, terminator);
+    }
+}
@@ -0,0 +1,96 @@
+use fregex::{types::{Strategy, Suggestion}, MultiRegex, Regex, RegexBuilder, RegexMatcher};
+
+const SINGLE_INPUTS: &'static [(&str, bool, &str, Option<(usize, usize)>)] = &[
+    ("^foo", true, "xfoo\nfoo", Some((5, 8))),
+    ("^foo", false, "xfoo\nfoo", None),
+    ("foo$", true, "foo bar\nbar foo\n", Some((12, 15))),
+    ("foo$", false, "foo bar\nbar foo\n", None),
+    ("^$", true, "a\n\nb", Some((2, 2))),
+    ("^foo$", true, "foo bar\nfoo\n", Some((8, 11))),
+    ("^(foo|bar)", true, "xbar\nbar", Some((5, 8))),
+    ("\\Afoo", true, "bar\nfoo", None),
+];
+
+const MULTI_INPUTS: &'static [(&[&str], &str, Option<(usize, usize, usize)>)] = &[
+    (&["^alpha", "beta$"], "beta alpha\nalpha", Some((11, 16, 0))),
+    (&["^alpha", "beta$"], "alpha beta\nbeta", Some((0, 5, 0))),
+    (&["^al+pha", "be?ta$"], "beta x\nbta\n", Some((7, 10, 1))),
+];
+
+/// Creates a builder with the multi-line mode set as requested.
+fn builder(multi_line: bool) -> RegexBuilder {
+    let mut builder = RegexBuilder::new();
+    builder.multi_line(multi_line);
+    return builder;
+}
+
+/// Test that the anchors of a single pattern match at every line in multi-line mode.
+#[test]
+fn test_single_multi_line() {
+    for (pattern, multi_line, text, expected) in SINGLE_INPUTS {
+        let regex = builder(*multi_line).build(pattern).unwrap();
+
+        let found = regex.find(text).map(|m| (m.start(), m.end()));
+        assert_eq!(*expected, found, "Unexpected match of {:?} in {:?}", pattern, text);
+    }
+}
+
+/// Test that the anchors of multiple patterns match at every line in multi-line mode.
+#[test]
+fn test_multi_multi_line() {
+    for (patterns, text, expected) in MULTI_INPUTS {
+        let regex = builder(true).build_multi(patterns).unwrap();
+
+        let found = regex.find_with_id(text).map(|(m, id)| (m.start(), m.end(), id));
+        assert_eq!(*expected, found, "Unexpected match of {:?} in {:?}", patterns, text);
+    }
+}
+
+/// Test that searching from an offset at the start of a line matches `^` in multi-line mode.
+#[test]
+fn test_find_at_multi_line() {
+    let regex = builder(true).build("^foo").unwrap();
+    let found = regex.find_at("foo\nfoo", 1).map(|m| (m.start(), m.end()));
+    assert_eq!(Some((4, 7)), found);
+}
+
+/// Test that the multi-line mode doesn't prevent the use of the heuristics, unlike the flag.
+#[test]
+fn test_multi_line_strategy() {
+    let regex = builder(true).build("foo$").unwrap();
+    assert_eq!(Strategy::Single(Suggestion::Prefix), regex.explain().strategy);
+}
+
+/// Test that the multi-line mode is kept by the serialized matchers.
+#[test]
+fn test_serialized_multi_line() {
+    for (pattern, multi_line, text, expected) in SINGLE_INPUTS {
+        let bytes = builder(*multi_line).build(pattern).unwrap().to_bytes();
+        let regex = Regex::from_bytes(&bytes).unwrap();
+
+        let found = regex.find(text).map(|m| (m.start(), m.end()));
+        assert_eq!(*expected, found, "Unexpected match of loaded {:?} in {:?}", pattern, text);
+    }
+
+    for (patterns, text, expected) in MULTI_INPUTS {
+        let bytes = builder(true).build_multi(patterns).unwrap().to_bytes();
+        let regex = MultiRegex::from_bytes(&bytes).unwrap();
+
+        let found = regex.find_with_id(text).map(|(m, id)| (m.start(), m.end(), id));
+        assert_eq!(*expected, found, "Unexpected match of loaded {:?} in {:?}", patterns, text);
+    }
+}
+
+/// Test that multiple patterns are matched around line terminators other than a line feed.
+#[test]
+fn test_multi_terminators() {
+    for terminator in [b'\0', b';', b'\r'] {
+        let mut builder = builder(true);
+        builder.line_terminator(terminator);
+        let regex = builder.build_multi(&[".$", "[ab]"]).unwrap();
+
+        let text = format!("a\nb{}", terminator as char);
+        assert_eq!(Some((0, 1, 1)), regex.find_with_id(&text).map(|(m, id)| (m.start(), m.end(), id)));
+        assert_eq!(Some((2, 3, 0)), regex.find_with_id_at(&text, 1).map(|(m, id)| (m.start(), m.end(), id)));
+    }
+}
@@ -1,4 +1,4 @@
-use fregex::{MultiRegex, RegexMatcher};
+use fregex::{types::Strategy, MultiRegex, RegexBuilder, RegexMatcher};
 
 
 const INPUTS: &'static [(&[&str], &str, Option<(usize, usize)>)] = &[
@@ -16,6 +16,11 @@ const INPUTS: &'static [(&[&str], &str, Option<(usize, usize)>)] = &[
     (&["pattern", "al?pha+"], "alpha beta gamma delta", Some((0, 5))),
     (&["longest", "or li*[txyz]eral"], "this should work with longest", Some((22, 29))),
     (&["[ac][xi][ea] is the best", "x*box", "card?"], "another cia is the best", Some((8, 23))),
+    (&["eaz", ".ea"], "bxeaz0", Some((1, 4))),
+
+    (&["éa", "b€", ".é"], "abab5bxéa0", Some((6, 9))),
+    (&["😀.", "a€b"], "é😀😀€", Some((2, 10))),
+    (&["€é€", "é\\d"], "ü€é€é4", Some((2, 10))),
 ];
 
 #[test]
@@ -32,3 +37,129 @@ fn test_multiple_patterns() {
         }
     }
 }
+
+const SET_INPUTS: &'static [(&[&str], &str, &[usize])] = &[
+    (&["alpha", "beta", "omega"], "alpha beta gamma delta", &[0, 1]),
+    (&["delta", "gamma", "alpha"], "alpha beta gamma delta", &[0, 1, 2]),
+    (&["he", "she", "hers"], "ushers", &[0, 1, 2]),
+    (&["not", "present"], "alpha beta gamma delta", &[]),
+
+    (&["al?pha+", "gam+a", "ep.ilon"], "alpha beta gamma delta", &[0, 1]),
+    (&["b[aeiou]ta", "alpha"], "alpha betx beta", &[0, 1]),
+    (&["(alpha|beta)", "d.lta$", "x+"], "alpha beta gamma delta", &[0, 1]),
+];
+
+#[test]
+fn test_set_matches() {
+    for (patterns, text, expected) in SET_INPUTS {
+        let regex = MultiRegex::new(patterns).unwrap();
+
+        let actual = regex.matches(text);
+
+        assert_eq!(expected.to_vec(), actual.iter().collect::<Vec<usize>>(), "Unexpected matches for {:?}", patterns);
+        assert_eq!(!expected.is_empty(), actual.matched_any(), "Unexpected result for {:?}", patterns);
+    }
+}
+
+const OVERLAPPING_INPUTS: &'static [(&[&str], &str, &[(usize, usize, usize)])] = &[
+    (&["he", "she", "hers"], "ushers", &[(1, 4, 1), (2, 4, 0), (2, 6, 2)]),
+    (&["aa", "aaa"], "aaaa", &[(0, 2, 0), (0, 3, 1), (1, 3, 0), (1, 4, 1), (2, 4, 0)]),
+    (&["alpha", "beta"], "gamma delta", &[]),
+
+    (&["h.rs", "she"], "ushers hers", &[(1, 4, 1), (2, 6, 0), (7, 11, 0)]),
+    (&["s[hx]e", "he+"], "ushers", &[(1, 4, 0), (2, 4, 1)]),
+    (&["^she", "he", "s+"], "she sells", &[(0, 3, 0), (0, 1, 2), (1, 3, 1), (4, 5, 2), (8, 9, 2)]),
+];
+
+#[test]
+fn test_overlapping_matches() {
+    for (patterns, text, expected) in OVERLAPPING_INPUTS {
+        let regex = MultiRegex::new(patterns).unwrap();
+
+        let actual: Vec<(usize, usize, usize)> = regex
+            .find_overlapping_iter(text)
+            .map(|(m, id)| (m.start(), m.end(), id))
+            .collect();
+
+        assert_eq!(expected.to_vec(), actual, "Unexpected matches for {:?}", patterns);
+    }
+}
+
+const GROUPED_INPUTS: &'static [(&[&str], Strategy, &str, Option<(usize, usize)>)] = &[
+    (&["^beta", "(gamma|delta)$"], Strategy::NothingMulti, "alpha beta gamma delta", Some((17, 22))),
+    (&["\\bta", "(ta|x)"], Strategy::NothingMulti, "alpha beta gamma delta", Some((8, 10))),
+    (&["(?P<g>al)pha", "(?P<g>be)ta"], Strategy::NothingMulti, "beta alpha", Some((0, 4))),
+    (&["gam(m|n)a", "del(t|d)a"], Strategy::NothingMulti, "alpha beta gamma delta", Some((11, 16))),
+    (&["del(t|d)a", "be(t|d)a", "x+"], Strategy::NothingMulti, "alpha beta gamma delta", Some((6, 10))),
+    (&["al(p|q)ha", "alpha|x"], Strategy::NothingMulti, "alpha beta", Some((0, 5))),
+    (&["alp(h|x)a beta", "al(p|q)"], Strategy::NothingMulti, "alpha beta", Some((0, 10))),
+
+    (&["(alpha|beta)", "gam+a"], Strategy::Hybrid, "alpha beta gamma delta", Some((0, 5))),
+    (&["gam+a", "(beta|delta)"], Strategy::Hybrid, "alpha beta gamma delta", Some((6, 10))),
+    (&["alpha", "beta", "(gamma|delta)"], Strategy::Hybrid, "delta beta", Some((0, 5))),
+    (&["del.a", "be(t|d)a"], Strategy::Hybrid, "beda delta", Some((0, 4))),
+    (&["alpha", "al(p|q)"], Strategy::Hybrid, "alpha", Some((0, 5))),
+    (&["al(p|q)", "alpha"], Strategy::Hybrid, "alpha", Some((0, 3))),
+    (&["alpha", "x", "^beta"], Strategy::Hybrid, "gamma beta", None),
+];
+
+#[test]
+fn test_grouped_patterns() {
+    for (patterns, strategy, text, expected) in GROUPED_INPUTS {
+        let regex = MultiRegex::new(patterns).unwrap();
+        assert_eq!(*strategy, regex.explain().strategy, "Wrong strategy for {:?}", patterns);
+
+        let actual = regex.find(text).map(|m| (m.start(), m.end()));
+        assert_eq!(*expected, actual, "Unexpected match for {:?}", patterns);
+
+        // Every match found by the library-supplied matcher must be present among the overlapping ones.
+        let actual: Vec<(usize, usize, usize)> = regex.find_overlapping_iter(text).map(|(m, id)| (m.start(), m.end(), id)).collect();
+        for (id, pattern) in patterns.iter().enumerate() {
+            for m in regex::Regex::new(pattern).unwrap().find_iter(text) {
+                assert!(actual.contains(&(m.start(), m.end(), id)), "Missing match at {} for {:?}", m.start(), pattern);
+            }
+        }
+    }
+}
+
+/// Test that matches far apart from each other are found in order, even if only one
+/// group of a hybrid matcher has matches nearby.
+#[test]
+fn test_distant_matches() {
+    let filler = "lorem ipsum dolor\n";
+    let mut text = String::new();
+    for (count, word) in [(3000, "delta"), (10, "beta"), (5000, "alpha"), (1, "gamma"), (20000, "beta")] {
+        text.push_str(&filler.repeat(count));
+        text.push_str(word);
+    }
+    text.push_str(&filler.repeat(100));
+
+    let patterns = ["alpha", "(gamma|delta)", "be(t|d)a"];
+    let regex = MultiRegex::new(&patterns).unwrap();
+    assert_eq!(Strategy::Hybrid, regex.explain().strategy);
+
+    // The library-supplied matcher of the alternation gives the expected matches.
+    let reference = regex::Regex::new(&patterns.join("|")).unwrap();
+    let expected: Vec<(usize, usize)> = reference.find_iter(&text).map(|m| (m.start(), m.end())).collect();
+
+    let mut actual = vec![];
+    let mut pos = 0;
+    while let Some(m) = regex.find(&text[pos..]) {
+        actual.push((pos + m.start(), pos + m.end()));
+        pos += m.end();
+    }
+
+    assert_eq!(expected, actual);
+}
+
+/// Test that the overlapping matches of literal patterns are found lazily, so that only
+/// the candidates before the taken matches are compared with the text.
+#[test]
+fn test_overlapping_matches_are_lazy() {
+    let regex = RegexBuilder::new().stats(true).build_multi(&["alpha", "beta"]).unwrap();
+    let text = "alpha beta ".repeat(1000);
+
+    let first: Vec<(usize, usize, usize)> = regex.find_overlapping_iter(&text).take(2).map(|(m, id)| (m.start(), m.end(), id)).collect();
+    assert_eq!(vec![(0, 5, 0), (6, 10, 1)], first);
+    assert!(regex.stats().wu_manber_candidates <= 2, "Too many candidates: {:?}", regex.stats());
+}
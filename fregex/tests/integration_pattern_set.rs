@@ -0,0 +1,158 @@
+use fregex::{types::{Error, Strategy}, MultiRegex, RegexMatcher};
+
+
+const TEXT: &str = "alpha beta gamma delta";
+
+/// Returns the coordinates of the first match of the set in the text.
+fn find(regex: &MultiRegex, text: &str) -> Option<(usize, usize)> {
+    return regex.find(text).map(|m| (m.start(), m.end()));
+}
+
+/// Test that added patterns extend the compiled matcher in place, unless its tables have to change.
+#[test]
+fn test_add_pattern_in_place() {
+    let mut regex = MultiRegex::new(&["gamma", "delta"]).unwrap();
+    assert_eq!(Some((11, 16)), find(&regex, TEXT));
+
+    // The new pattern is just as long, so the Wu-Manber tables are extended in place.
+    assert_eq!(2, regex.add("alpha").unwrap());
+    assert_eq!(Some((0, 5)), find(&regex, TEXT));
+    assert_eq!(Strategy::LiteralMulti, regex.explain().strategy);
+    assert_eq!(3, regex.len());
+
+    // A shorter pattern changes the minimum length, so the tables are rebuilt.
+    assert_eq!(3, regex.add("beta").unwrap());
+    assert_eq!(4, regex.explain().wu_manber.unwrap().min_length);
+    assert_eq!(Some((0, 5)), find(&regex, TEXT));
+}
+
+/// Test that removed patterns are no longer matched, and the other patterns keep their ids.
+#[test]
+fn test_remove_pattern_keeps_ids() {
+    let mut regex = MultiRegex::new(&["alpha", "be.a", "delta"]).unwrap();
+    assert_eq!(Strategy::LongestMulti, regex.explain().strategy);
+
+    assert!(regex.remove(0));
+    assert!(!regex.remove(0));
+    assert!(!regex.remove(10));
+    assert_eq!(Some((6, 10)), find(&regex, TEXT));
+    assert_eq!(vec![1, 2], regex.matches(TEXT).iter().collect::<Vec<usize>>());
+
+    assert!(regex.remove(1));
+    assert_eq!(Some((17, 22)), find(&regex, TEXT));
+    assert_eq!(1, regex.len());
+
+    // The remaining pattern keeps its id, and new ones continue the numbering.
+    assert_eq!(3, regex.add("gam+a").unwrap());
+    assert!(regex.remove(2));
+    assert_eq!(Some((11, 16)), find(&regex, TEXT));
+
+    let patterns: Vec<String> = regex.explain().patterns.iter().map(|p| p.pattern.clone()).collect();
+    assert_eq!(vec!["gam+a"], patterns);
+}
+
+/// Test that the strategy of the set follows the added and removed patterns.
+#[test]
+fn test_strategy_changes() {
+    let mut regex = MultiRegex::new(&["alpha", "beta"]).unwrap();
+
+    let id = regex.add("(gamma|delta)").unwrap();
+    assert_eq!(Strategy::Hybrid, regex.explain().strategy);
+    assert_eq!(Some((0, 5)), find(&regex, TEXT));
+
+    regex.remove(id);
+    assert_eq!(Strategy::LiteralMulti, regex.explain().strategy);
+
+    // Patterns whose literals are too short for the Wu-Manber tables are matched separately.
+    regex.add("a").unwrap();
+    assert_eq!(Strategy::Hybrid, regex.explain().strategy);
+    assert_eq!(Some((4, 5)), find(&regex, "xyz a"));
+
+    // Both groups of the hybrid matcher can be extended in place.
+    regex.add("gam+a").unwrap();
+    regex.add("de(l|x)ta").unwrap();
+    assert_eq!(Strategy::Hybrid, regex.explain().strategy);
+    assert_eq!(vec![1, 3, 4, 5], regex.matches("beta gamma delta").iter().collect::<Vec<usize>>());
+
+    assert!(regex.remove(0));
+    assert!(regex.remove(1));
+    assert!(regex.remove(4));
+    assert_eq!(Strategy::NothingMulti, regex.explain().strategy);
+    assert!(regex.remove(3));
+    assert!(regex.remove(5));
+    assert!(regex.is_empty());
+    assert_eq!(None, find(&regex, TEXT));
+}
+
+/// Test that an invalid pattern is rejected without changing the set.
+#[test]
+fn test_invalid_pattern_is_rejected() {
+    let mut regex = MultiRegex::new(&["alpha", "beta"]).unwrap();
+
+    assert!(regex.add("(alpha").is_err());
+    assert_eq!(2, regex.len());
+    assert_eq!(2, regex.add("gamma").unwrap());
+}
+
+/// Test that a set is serialized without its removed patterns, and can be modified after loading.
+#[test]
+fn test_serialize_after_removal() {
+    let mut regex = MultiRegex::new(&["alpha", "beta", "delta"]).unwrap();
+    regex.remove(0);
+
+    let mut loaded = MultiRegex::from_bytes(&regex.to_bytes()).unwrap();
+    assert_eq!(2, loaded.len());
+    assert_eq!(Some((6, 10)), find(&loaded, TEXT));
+
+    // The loaded set can be modified just like a compiled one.
+    assert_eq!(2, loaded.add("al.ha").unwrap());
+    assert_eq!(Some((0, 5)), find(&loaded, TEXT));
+    assert!(loaded.remove(0));
+    assert_eq!(Some((0, 5)), find(&loaded, TEXT));
+}
+
+/// Test that the ids of the matching patterns are reported, and kept after modifications.
+#[test]
+fn test_find_with_id() {
+    let mut regex = MultiRegex::new(&["gamma", "b.ta", "delta"]).unwrap();
+    assert_eq!(Some((6, 1)), regex.find_with_id(TEXT).map(|(m, id)| (m.start(), id)));
+
+    // The ids of the patterns are kept after removals and additions.
+    regex.remove(1);
+    assert_eq!(Some((11, 0)), regex.find_with_id(TEXT).map(|(m, id)| (m.start(), id)));
+    regex.add("al.ha").unwrap();
+    assert_eq!(Some((0, 3)), regex.find_with_id(TEXT).map(|(m, id)| (m.start(), id)));
+    regex.remove(0);
+    assert_eq!(Some((0, 3)), regex.find_with_id(TEXT).map(|(m, id)| (m.start(), id)));
+    assert!(regex.find_with_id("beta").is_none());
+
+    let single = fregex::Regex::new("del.a").unwrap();
+    assert_eq!(Some((17, 0)), single.find_with_id(TEXT).map(|(m, id)| (m.start(), id)));
+}
+
+/// Test that removing patterns in place leaves the same shift values as compiling the rest.
+#[test]
+fn test_remove_keeps_shifts() {
+    let patterns = ["alpha", "alphabet", "beta", "betamax", "phase", "gamma", "mammal", "delta"];
+    let mut regex = MultiRegex::new(&patterns).unwrap();
+
+    for id in [0, 3, 5] {
+        assert!(regex.remove(id));
+    }
+    let rest = ["alphabet", "beta", "phase", "mammal", "delta"];
+    let compiled = MultiRegex::new(&rest).unwrap();
+
+    let (removed, compiled) = (regex.explain().wu_manber.unwrap(), compiled.explain().wu_manber.unwrap());
+    assert_eq!(compiled.shift_table_size, removed.shift_table_size);
+    assert_eq!(compiled.prefix_table_size, removed.prefix_table_size);
+
+    let text = "an alpha gamma betamax mammal, with a phase delta";
+    let found: Vec<(usize, usize)> = regex.find_overlapping_iter(text).map(|(m, _)| (m.start(), m.end())).collect();
+    assert_eq!(vec![(15, 19), (23, 29), (38, 43), (44, 49)], found);
+}
+
+/// Test that a set can't be created without patterns.
+#[test]
+fn test_empty_set_is_rejected() {
+    assert!(matches!(MultiRegex::new(&[]), Err(Error::Empty)));
+}
@@ -0,0 +1,125 @@
+use fregex::{Regex, MultiRegex, RegexMatcher, types::Error};
+
+const SINGLE_INPUTS: &'static [(&str, &str)] = &[
+    ("pattern", "text with pattern"),
+    ("\\$\\(\\)\\$", "text with $()$ chars"),
+    ("p..ce", "piece peace pounce"),
+    ("C[a-z]*a", "Short parts Circa"),
+    ("ba(se)+", "multiple ba ba but only one is base"),
+    ("(foo|bar)baz", "foobar\nbarbaz"),
+    ("[^s]yy*", "text with \nyd"),
+];
+
+const MULTI_INPUTS: &'static [(&[&str], &str)] = &[
+    (&["alpha", "beta"], "alpha beta gamma delta"),
+    (&["gamma", "beta", "delta", "alpha"], "alpha beta gamma delta"),
+    (&["longest", "or li*[txyz]eral"], "this should work with longest"),
+    (&["pat+er*n", "(text)? not even prefix"], "text with pattern"),
+    (&["single"], "a single pattern"),
+    (&["alp\\.ha", "be(t|d)a", "gamma"], "alp.ha beda gamma"),
+    (&["alp\\.ha", "be(t|d)a", "gam+a"], "gamma beda alp.ha"),
+];
+
+/// Returns the coordinates of the first match of the matcher in the text.
+fn coordinates(matcher: &dyn RegexMatcher, text: &str) -> Option<(usize, usize)> {
+    return matcher.find(text).map(|m| (m.start(), m.end()));
+}
+
+/// Test that a deserialized single pattern matcher finds the same matches
+/// and uses the same strategy as the original one.
+#[test]
+fn test_roundtrip_single() {
+    for (pattern, text) in SINGLE_INPUTS {
+        let original = Regex::new(pattern).unwrap();
+        let loaded = Regex::from_bytes(&original.to_bytes()).unwrap();
+
+        assert_eq!(coordinates(&original, text), coordinates(&loaded, text), "Different match for {:?}", pattern);
+        assert_eq!(original.explain().strategy, loaded.explain().strategy, "Different strategy for {:?}", pattern);
+    }
+}
+
+/// Test that a deserialized multi-pattern matcher finds the same matches
+/// and uses the same strategy as the original one.
+#[test]
+fn test_roundtrip_multi() {
+    for (patterns, text) in MULTI_INPUTS {
+        let original = MultiRegex::new(patterns).unwrap();
+        let loaded = MultiRegex::from_bytes(&original.to_bytes()).unwrap();
+
+        assert_eq!(coordinates(&original, text), coordinates(&loaded, text), "Different match for {:?}", patterns);
+        assert_eq!(original.explain().strategy, loaded.explain().strategy, "Different strategy for {:?}", patterns);
+
+        let loaded_patterns: Vec<String> = loaded.explain().patterns.into_iter().map(|p| p.pattern).collect();
+        assert_eq!(patterns.to_vec(), loaded_patterns, "Different patterns for {:?}", patterns);
+    }
+}
+
+/// Test that malformed, truncated or mismatched data is rejected instead of being loaded.
+#[test]
+fn test_invalid_data() {
+    let single = Regex::new("p..ce").unwrap().to_bytes();
+    let multi = MultiRegex::new(&["alpha", "beta"]).unwrap().to_bytes();
+
+    // Wrong magic bytes, wrong version and wrong kind.
+    assert!(matches!(Regex::from_bytes(b"NOPE"), Err(Error::Format(_))));
+    let mut versioned = single.clone();
+    versioned[4] = 0xff;
+    assert!(matches!(Regex::from_bytes(&versioned), Err(Error::Format(_))));
+    assert!(matches!(Regex::from_bytes(&multi), Err(Error::Format(_))));
+    assert!(matches!(MultiRegex::from_bytes(&single), Err(Error::Format(_))));
+
+    // Every truncation of valid data must be rejected.
+    for length in 0..multi.len() {
+        assert!(MultiRegex::from_bytes(&multi[..length]).is_err(), "Accepted truncation to {} bytes", length);
+    }
+
+    // Trailing data is rejected too.
+    let mut extended = single.clone();
+    extended.push(0);
+    assert!(matches!(Regex::from_bytes(&extended), Err(Error::Format(_))));
+}
+
+/// Test that a stored pattern which doesn't compile anymore is rejected.
+#[test]
+fn test_revalidation() {
+    let bytes = Regex::new("p..ce").unwrap().to_bytes();
+
+    // Replace the stored pattern with an invalid one of the same length.
+    let position = bytes.windows(5).position(|w| w == b"p..ce").unwrap();
+    let mut corrupted = bytes.clone();
+    corrupted[position .. position + 5].copy_from_slice(b"p(.ce");
+
+    assert!(matches!(Regex::from_bytes(&corrupted), Err(Error::Syntax(_))));
+}
+
+/// Test that deeply nested matchers are rejected instead of exhausting the stack.
+#[test]
+fn test_nested_data() {
+    // The header of the format, with the magic bytes, the version, the kind and the options.
+    let header = &Regex::new("p..ce").unwrap().to_bytes()[..10];
+
+    // Boundary checking matchers wrapping each other.
+    let mut bounded = header.to_vec();
+    for _ in 0..500_000 {
+        bounded.extend_from_slice(&[9, 0]);
+    }
+    assert!(matches!(Regex::from_bytes(&bounded), Err(Error::Format(_))));
+
+    // Multi-pattern matchers containing each other.
+    let mut multi = MultiRegex::new(&["alpha", "beta"]).unwrap().to_bytes()[..10].to_vec();
+    for _ in 0..100_000 {
+        multi.push(7);
+        multi.extend_from_slice(&1u64.to_le_bytes());
+    }
+    assert!(matches!(MultiRegex::from_bytes(&multi), Err(Error::Format(_))));
+}
+
+/// Test that a single pattern matcher can't be loaded from a multi-pattern matcher.
+#[test]
+fn test_single_rejects_multi() {
+    let mut bytes = MultiRegex::new(&["alpha", "beta"]).unwrap().to_bytes();
+
+    // Claim that the data is of a single pattern matcher.
+    bytes[6] = 0;
+    assert!(matches!(Regex::from_bytes(&bytes), Err(Error::Format(_))));
+}
@@ -16,8 +16,34 @@ const INPUTS: &'static [(&str, &str, Option<(usize, usize)>)] = &[
     ("C[a-z]*a", "Short parts Circa", Some((12, 17))),
     ("[Pp]refix.*:", "Long string with a prefix: somewhere", Some((19, 26))),
 
+    ("a\\.b(c|d)", "axbc a.bd", Some((5, 9))),
+    ("(foo|bar)baz", "foobar\nbarbaz", Some((7, 13))),
+    ("(foo|bar)? baz", "foo\nbar baz", Some((4, 11))),
+
     ("a\nb+", "text with a\nbbb", Some((10, 15))),
     ("[^s]yy*", "text with \nyd", Some((10, 12))),
+
+    ("é[0-9]+ü", "aé12ü€", Some((1, 7))),
+    ("x(é|ü)+", "€xéü!", Some((3, 8))),
+    ("[aé]+b", "😀éab€", Some((4, 8))),
+
+    ("\\<foo", "a<foo foo", Some((2, 5))),
+    ("foo\\>", "foo>x foo", Some((0, 3))),
+    ("\\b{start}bar", "a<bar", Some((2, 5))),
+    ("bar\\b{end}", "barx bar", Some((5, 8))),
+    ("\\b{start-half}baz", "xbaz <baz", Some((6, 9))),
+    ("baz\\b{end-half}", "bazx baz>", Some((5, 8))),
+
+    ("xé+*y", "xy", Some((0, 2))),
+    ("ab+*\\.?c", "ac", Some((0, 2))),
+    ("a\n+*b", "ab", Some((0, 2))),
+    ("ab+*", "a", Some((0, 1))),
+
+    ("\\x41B", "xAB", Some((1, 3))),
+    ("\\u0041B", "xAB", Some((1, 3))),
+    ("\\U00000041B", "xAB", Some((1, 3))),
+    ("ab\\x41cd", "abAc abAcd", Some((5, 10))),
+    ("\\x{41}B", "xAB", Some((1, 3))),
 ];
 
 /// Test that on a number of input combinations, the (single-pattern) matcher
@@ -0,0 +1,81 @@
+use fregex::{Regex, MultiRegex, RegexBuilder, RegexMatcher, types::Stats};
+
+const SINGLE_INPUTS: &'static [(&str, &str, (u64, u64))] = &[
+    ("pattern", "text with pattern", (1, 1)),
+    ("p..ce", "piece peace pounce", (1, 1)),
+    ("[ai][cx]e", "words with the letter e but only axe matches", (5, 1)),
+    ("ba(se)+", "multiple ba ba but only one is base", (1, 1)),
+    ("(text)? x", "no match\nbut a x", (1, 1)),
+    ("(text)? x+", "a x", (0, 0)),
+];
+
+const MULTI_INPUTS: &'static [(&[&str], &str, (u64, u64), (u64, u64))] = &[
+    (&["alpha", "beta"], "alpha beta gamma", (0, 0), (1, 1)),
+    (&["al?pha+", "pattern"], "alpha beta", (1, 1), (1, 1)),
+    (&["xy(a|b)", "not longest (text)?"], "xyc xya", (2, 1), (2, 2)),
+];
+
+/// Creates a builder that counts the candidates.
+fn counting() -> RegexBuilder {
+    let mut builder = RegexBuilder::new();
+    builder.stats(true);
+    return builder;
+}
+
+/// Test that the candidates of the prefilter of a single pattern are counted,
+/// with the ones confirmed by the full pattern.
+#[test]
+fn test_single_stats() {
+    for (pattern, text, (candidates, confirmed)) in SINGLE_INPUTS {
+        let regex = counting().build(pattern).unwrap();
+        assert_eq!(Stats::default(), regex.stats(), "Unexpected counters before searching for {:?}", pattern);
+
+        regex.find(text);
+
+        let stats = regex.stats();
+        assert_eq!((*candidates, *confirmed), (stats.candidates, stats.confirmed), "Unexpected counters for {:?}", pattern);
+    }
+}
+
+/// Test that the candidates of the Wu-Manber algorithm are counted separately
+/// from the ones verified by the full patterns.
+#[test]
+fn test_multi_stats() {
+    for (patterns, text, (candidates, confirmed), (wu_manber_candidates, wu_manber_confirmed)) in MULTI_INPUTS {
+        let regex = counting().build_multi(patterns).unwrap();
+
+        regex.find(text);
+
+        let stats = regex.stats();
+        assert_eq!((*candidates, *confirmed), (stats.candidates, stats.confirmed), "Unexpected counters for {:?}", patterns);
+        assert_eq!(
+            (*wu_manber_candidates, *wu_manber_confirmed), (stats.wu_manber_candidates, stats.wu_manber_confirmed),
+            "Unexpected Wu-Manber counters for {:?}", patterns
+        );
+    }
+}
+
+/// Test that the counters add up across searches.
+#[test]
+fn test_accumulated_stats() {
+    let regex = counting().build("p..ce").unwrap();
+    for _ in 0..3 {
+        regex.find("piece peace pounce");
+    }
+
+    let stats = regex.stats();
+    assert_eq!((3, 3), (stats.candidates, stats.confirmed));
+    assert_eq!(stats, Stats { candidates: 3, confirmed: 3, ..Stats::default() } + Stats::default());
+}
+
+/// Test that nothing is counted unless the counters were enabled.
+#[test]
+fn test_stats_disabled() {
+    let regex = Regex::new("p..ce").unwrap();
+    regex.find("piece peace pounce");
+    assert_eq!(Stats::default(), regex.stats());
+
+    let regex = MultiRegex::new(&["alpha", "al?pha+"]).unwrap();
+    regex.find("alpha beta");
+    assert_eq!(Stats::default(), regex.stats());
+}
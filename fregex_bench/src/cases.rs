@@ -0,0 +1,76 @@
+use fregex::types::{Strategy::{self, *}, Suggestion};
+
+use crate::corpus::Corpus;
+
+/// A single benchmark case: a set of patterns searched for in a given corpus.
+pub struct Case {
+    /// The corpus to search in.
+    pub corpus: Corpus,
+    /// The strategy the patterns are expected to be compiled into. It is checked
+    /// against the explanation of the compiled matcher before the case is run.
+    pub strategy: Strategy,
+    /// The pattern(s) to search for. A single pattern is compiled into a `Regex`,
+    /// multiple patterns are compiled into a `MultiRegex`.
+    pub patterns: &'static [&'static str],
+}
+
+/// Every benchmark case. Each corpus has a representative pattern (set)
+/// for every single and multi-pattern matcher implementation.
+pub const CASES: &[Case] = &[
+    Case { corpus: Corpus::English, strategy: Single(Suggestion::Literal), patterns: &["Sherlock"] },
+    Case { corpus: Corpus::English, strategy: Single(Suggestion::Longest), patterns: &["Wat[sz]on"] },
+    Case { corpus: Corpus::English, strategy: Single(Suggestion::Prefix), patterns: &["Lon(don|gon)"] },
+    Case { corpus: Corpus::English, strategy: Single(Suggestion::Suffix), patterns: &["(Holmes|Watson) was"] },
+    Case { corpus: Corpus::English, strategy: Single(Suggestion::Nothing), patterns: &["(Holmes|Watson) (was|had)"] },
+    Case { corpus: Corpus::English, strategy: LiteralMulti, patterns: &["Sherlock", "Watson", "London"] },
+    Case { corpus: Corpus::English, strategy: LongestMulti, patterns: &["Sher.ock", "Wat[sz]on", "London"] },
+    Case { corpus: Corpus::English, strategy: NothingMulti, patterns: &["(Holmes|Watson) was", "Sherlock (Holmes)?"] },
+    Case { corpus: Corpus::English, strategy: Hybrid, patterns: &["Sherlock", "(Holmes|Watson) was"] },
+
+    Case { corpus: Corpus::Logs, strategy: Single(Suggestion::Literal), patterns: &["ERROR"] },
+    Case { corpus: Corpus::Logs, strategy: Single(Suggestion::Longest), patterns: &["status=50."] },
+    Case { corpus: Corpus::Logs, strategy: Single(Suggestion::Prefix), patterns: &["path=/api/(users|orders)"] },
+    Case { corpus: Corpus::Logs, strategy: Single(Suggestion::Suffix), patterns: &["(404|500) took="] },
+    Case { corpus: Corpus::Logs, strategy: Single(Suggestion::Nothing), patterns: &["(WARN|ERROR) \\[worker-1[0-5]+"] },
+    Case { corpus: Corpus::Logs, strategy: LiteralMulti, patterns: &["ERROR", "status=500", "took=1999ms"] },
+    Case { corpus: Corpus::Logs, strategy: LongestMulti, patterns: &["status=50.", "took=19..ms", "ERROR"] },
+    Case { corpus: Corpus::Logs, strategy: NothingMulti, patterns: &["(WARN|ERROR) \\[worker", "id=(dead|beef)"] },
+    Case { corpus: Corpus::Logs, strategy: Hybrid, patterns: &["ERROR", "id=(dead|beef)"] },
+
+    Case { corpus: Corpus::Dna, strategy: Single(Suggestion::Literal), patterns: &["GATTACA"] },
+    Case { corpus: Corpus::Dna, strategy: Single(Suggestion::Longest), patterns: &["GATT.CA"] },
+    Case { corpus: Corpus::Dna, strategy: Single(Suggestion::Prefix), patterns: &["GAT(TA|CG)CA"] },
+    Case { corpus: Corpus::Dna, strategy: Single(Suggestion::Suffix), patterns: &["(GATT|TGTA)ACA"] },
+    Case { corpus: Corpus::Dna, strategy: Single(Suggestion::Nothing), patterns: &["(GATTACA|TGTAATC)"] },
+    Case { corpus: Corpus::Dna, strategy: LiteralMulti, patterns: &["GATTACA", "TGTAATC", "CCCGGGA"] },
+    Case { corpus: Corpus::Dna, strategy: LongestMulti, patterns: &["GATT.CA", "TGTA[AT]TC", "CCCGGGA"] },
+    Case { corpus: Corpus::Dna, strategy: NothingMulti, patterns: &["(GATT|CCCG)ACA", "(TGTA|CCCG)ATC"] },
+    Case { corpus: Corpus::Dna, strategy: Hybrid, patterns: &["GATTACA", "(TGTA|CCCG)ATC"] },
+
+    Case { corpus: Corpus::Random, strategy: Single(Suggestion::Literal), patterns: &["xyz"] },
+    Case { corpus: Corpus::Random, strategy: Single(Suggestion::Longest), patterns: &["ab.cd"] },
+    Case { corpus: Corpus::Random, strategy: Single(Suggestion::Prefix), patterns: &["ab(c|d)"] },
+    Case { corpus: Corpus::Random, strategy: Single(Suggestion::Suffix), patterns: &["(ab|cd)xyz"] },
+    Case { corpus: Corpus::Random, strategy: Single(Suggestion::Nothing), patterns: &["[0-9]{3}"] },
+    Case { corpus: Corpus::Random, strategy: LiteralMulti, patterns: &["xyz", "abc", "qrs"] },
+    Case { corpus: Corpus::Random, strategy: LongestMulti, patterns: &["ab.cd", "xyz", "qr[st]uv"] },
+    Case { corpus: Corpus::Random, strategy: NothingMulti, patterns: &["[0-9]{3}", "x(y|z)"] },
+    Case { corpus: Corpus::Random, strategy: Hybrid, patterns: &["xyz", "[0-9]{3}"] },
+];
+
+impl Case {
+    /// Returns the name of the matcher implementation of the expected strategy.
+    pub fn matcher(&self) -> &'static str {
+        return match self.strategy {
+            Single(Suggestion::Literal) => "LiteralMatcher",
+            Single(Suggestion::Longest) => "LongestMatcher",
+            Single(Suggestion::Prefix) => "PrefixMatcher",
+            Single(Suggestion::Suffix) => "SuffixMatcher",
+            Single(Suggestion::Nothing) => "NothingMatcher",
+            LiteralMulti => "LiteralMultiMatcher",
+            LongestMulti => "LongestMultiMatcher",
+            NothingMulti => "NothingMultiMatcher",
+            Hybrid => "HybridMultiMatcher",
+        };
+    }
+}
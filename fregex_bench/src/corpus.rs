@@ -0,0 +1,138 @@
+/// The kinds of generated text the benchmarks can be run on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Corpus {
+    /// English-like prose built from a weighted vocabulary.
+    English,
+    /// Application log lines with timestamps, levels and key-value pairs.
+    Logs,
+    /// A random nucleotide sequence, broken into fixed-width lines.
+    Dna,
+    /// Uniformly random ASCII bytes. The matchers operate on `&str`,
+    /// so the bytes are restricted to the valid single-byte UTF-8 range.
+    Random,
+}
+
+/// A small, deterministic xorshift pseudo-random number generator, so that
+/// every benchmark run works on exactly the same input.
+pub struct Rng {
+    state: u64,
+}
+
+/// Words used for the English corpus. Frequent words are listed multiple times.
+const WORDS: &[&str] = &[
+    "the", "the", "the", "the", "of", "of", "of", "and", "and", "and", "to", "to", "a", "a",
+    "in", "in", "is", "it", "that", "was", "for", "on", "with", "as", "by", "he", "she", "they",
+    "at", "from", "this", "have", "had", "not", "but", "which", "were", "when", "there",
+    "people", "time", "year", "world", "house", "water", "between", "through", "another",
+    "government", "number", "something", "important", "information", "development",
+    "especially", "particular", "experience", "understand", "everything", "Sherlock",
+    "Holmes", "Watson", "London", "evening", "question", "morning", "whatever", "himself",
+];
+
+/// Log levels used for the log corpus, weighted by how often they usually appear.
+const LEVELS: &[&str] = &["INFO", "INFO", "INFO", "INFO", "DEBUG", "DEBUG", "WARN", "ERROR"];
+
+/// Request paths used for the log corpus.
+const PATHS: &[&str] = &[
+    "/api/users", "/api/orders", "/api/items", "/static/app.js", "/login", "/logout", "/health",
+];
+
+impl Rng {
+    /// Creates a new generator from the given seed. The seed must not be zero.
+    pub fn new(seed: u64) -> Rng {
+        return Rng { state: seed };
+    }
+
+    /// Returns the next pseudo-random number.
+    pub fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        return self.state;
+    }
+
+    /// Returns a pseudo-random number in the `0..bound` range.
+    pub fn below(&mut self, bound: usize) -> usize {
+        return (self.next() % bound as u64) as usize;
+    }
+
+    /// Returns a pseudo-random element of the given slice.
+    pub fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        return items[self.below(items.len())];
+    }
+}
+
+impl Corpus {
+    /// Every available corpus, in the order they are benchmarked.
+    pub const ALL: [Corpus; 4] = [Corpus::English, Corpus::Logs, Corpus::Dna, Corpus::Random];
+
+    /// Returns the name of the corpus, as displayed in the report.
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Corpus::English => "english",
+            Corpus::Logs => "logs",
+            Corpus::Dna => "dna",
+            Corpus::Random => "random",
+        };
+    }
+
+    /// Generates a text of (approximately) the given size in bytes.
+    pub fn generate(&self, size: usize) -> String {
+        let mut rng = Rng::new(0x2545_F491_4F6C_DD1D);
+        let mut text = String::with_capacity(size + 128);
+
+        while text.len() < size {
+            match self {
+                Corpus::English => push_sentence(&mut rng, &mut text),
+                Corpus::Logs => push_log_line(&mut rng, &mut text),
+                Corpus::Dna => push_dna_line(&mut rng, &mut text),
+                Corpus::Random => text.push((rng.below(127) + 1) as u8 as char),
+            }
+        }
+
+        return text;
+    }
+}
+
+/// Appends a sentence of random words to the text.
+fn push_sentence(rng: &mut Rng, text: &mut String) {
+    let length = 5 + rng.below(15);
+
+    for i in 0..length {
+        let word = rng.pick(WORDS);
+
+        // Capitalize the first word of the sentence.
+        if i == 0 {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                text.push(first.to_ascii_uppercase());
+                text.push_str(chars.as_str());
+            }
+        } else {
+            text.push(' ');
+            text.push_str(word);
+        }
+    }
+
+    // Occasionally break the paragraph.
+    text.push_str(if rng.below(8) == 0 { ".\n" } else { ". " });
+}
+
+/// Appends a single log line to the text.
+fn push_log_line(rng: &mut Rng, text: &mut String) {
+    let line = format!(
+        "2022-{:02}-{:02}T{:02}:{:02}:{:02} {} [worker-{}] request id={:08x} path={} status={} took={}ms\n",
+        rng.below(12) + 1, rng.below(28) + 1, rng.below(24), rng.below(60), rng.below(60),
+        rng.pick(LEVELS), rng.below(16), rng.next() as u32, rng.pick(PATHS),
+        [200, 200, 200, 201, 304, 404, 500][rng.below(7)], rng.below(2000)
+    );
+    text.push_str(&line);
+}
+
+/// Appends a single, 60 character long line of nucleotides to the text.
+fn push_dna_line(rng: &mut Rng, text: &mut String) {
+    for _ in 0..60 {
+        text.push(['A', 'C', 'G', 'T'][rng.below(4)]);
+    }
+    text.push('\n');
+}
@@ -0,0 +1,151 @@
+use clap::Parser;
+use fregex::{Regex, MultiRegex, RegexMatcher};
+use regex::Regex as OriginalRegex;
+use std::{process::exit, time::Instant};
+
+use crate::{cases::{Case, CASES}, corpus::Corpus, measure::{count_matches, measure, Measurement}};
+
+mod cases;
+mod corpus;
+mod measure;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// The size of each generated corpus in bytes
+    #[arg(short = 's', long = "size", default_value_t = 8_000_000)]
+    size: usize,
+
+    /// How many times each case is run. The fastest run is reported
+    #[arg(short = 'i', long = "iterations", default_value_t = 5)]
+    iterations: usize,
+
+    /// Only run cases on the given corpus (english, logs, dna or random)
+    #[arg(short = 'c', long = "corpus")]
+    corpus: Option<String>,
+
+    /// Only run cases whose matcher name contains the given string
+    #[arg(short = 'm', long = "matcher")]
+    matcher: Option<String>,
+
+    /// Compare compiling large pattern sets against loading them in serialized form
+    #[arg(long = "serialization", default_value_t = false)]
+    serialization: bool,
+}
+
+/// Compiles the patterns of a case into the fregex matcher, and checks its strategy.
+fn compile_fregex(case: &Case) -> Box<dyn RegexMatcher> {
+    let result: Result<Box<dyn RegexMatcher>, _> = if case.patterns.len() > 1 {
+        MultiRegex::new(case.patterns).map(|m| Box::new(m) as Box<dyn RegexMatcher>)
+    } else {
+        Regex::new(case.patterns[0]).map(|m| Box::new(m) as Box<dyn RegexMatcher>)
+    };
+
+    let matcher = match result {
+        Ok(matcher) => matcher,
+        Err(_) => { println!("Error parsing patterns {:?}!", case.patterns); exit(1); }
+    };
+
+    // The case would be reported under the wrong matcher if the heuristics changed.
+    let strategy = matcher.explain().strategy;
+    if strategy != case.strategy {
+        println!("Patterns {:?} compiled into {:?} instead of {:?}!", case.patterns, strategy, case.strategy);
+        exit(1);
+    }
+
+    return matcher;
+}
+
+/// Compiles the patterns of a case into the library-supplied matcher. Multiple
+/// patterns are joined into a single alternation.
+fn compile_original(case: &Case) -> OriginalRegex {
+    let pattern = case.patterns
+        .iter()
+        .map(|p| format!("(?:{})", p))
+        .collect::<Vec<String>>()
+        .join("|");
+
+    match OriginalRegex::new(&pattern) {
+        Ok(matcher) => return matcher,
+        Err(_) => { println!("Error parsing patterns {:?}!", case.patterns); exit(1); }
+    }
+}
+
+/// Prints a single row of the report.
+fn report(case: &Case, size: usize, custom: &Measurement, original: &Measurement) {
+    let custom_speed = custom.throughput(size);
+    let original_speed = original.throughput(size);
+
+    // Differing match counts point to a bug in one of the heuristics.
+    let note = if custom.matches != original.matches {
+        format!("  MISMATCH ({} vs {})", custom.matches, original.matches)
+    } else {
+        String::new()
+    };
+
+    println!(
+        "{:<8} {:<20} {:>10.1} {:>10.1} {:>7.2}x {:>9}  {:?}{}",
+        case.corpus.name(), case.matcher(), custom_speed, original_speed,
+        custom_speed / original_speed, custom.matches, case.patterns, note
+    );
+}
+
+/// Measures how long it takes to compile a set of generated patterns with the
+/// given shape, and how long it takes to load the same set in serialized form.
+fn report_serialization(name: &str, count: usize, shape: fn(usize) -> String) {
+    let patterns: Vec<String> = (0..count).map(shape).collect();
+    let patterns: Vec<&str> = patterns.iter().map(|p| p.as_str()).collect();
+
+    let start = Instant::now();
+    let compiled = MultiRegex::new(&patterns).unwrap();
+    let compile_time = start.elapsed();
+
+    let bytes = compiled.to_bytes();
+    let start = Instant::now();
+    let loaded = MultiRegex::from_bytes(&bytes).unwrap();
+    let load_time = start.elapsed();
+
+    println!(
+        "{:<8} {:>6} patterns {:>10.2?} compile {:>10.2?} load {:>9} bytes  {:?}",
+        name, count, compile_time, load_time, bytes.len(), loaded.explain().strategy
+    );
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if args.serialization {
+        report_serialization("literal", 10_000, |i| format!("word{:05}x", i));
+        report_serialization("longest", 1_000, |i| format!("wo.d{:04}x+", i));
+        report_serialization("nothing", 1_000, |i| format!("(a|b){:04}", i));
+        return;
+    }
+
+    println!("corpus   matcher                  fregex      regex    ratio   matches  patterns");
+
+    for corpus in Corpus::ALL {
+        if args.corpus.as_ref().is_some_and(|c| c != corpus.name()) {
+            continue;
+        }
+
+        let text = corpus.generate(args.size);
+
+        for case in CASES.iter().filter(|case| case.corpus == corpus) {
+            if args.matcher.as_ref().is_some_and(|m| !case.matcher().contains(m.as_str())) {
+                continue;
+            }
+
+            let custom = compile_fregex(case);
+            let original = compile_original(case);
+
+            let custom_result = measure(args.iterations, || {
+                count_matches(&text, |t| custom.find(t).map(|m| (m.start(), m.end())))
+            });
+            let original_result = measure(args.iterations, || {
+                count_matches(&text, |t| original.find(t).map(|m| (m.start(), m.end())))
+            });
+
+            report(case, text.len(), &custom_result, &original_result);
+        }
+    }
+}
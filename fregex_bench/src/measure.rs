@@ -0,0 +1,61 @@
+use std::{hint::black_box, time::{Duration, Instant}};
+
+/// The result of measuring a single search function.
+pub struct Measurement {
+    /// The fastest run out of every iteration.
+    pub best: Duration,
+    /// The number of matches found during a single run.
+    pub matches: usize,
+}
+
+impl Measurement {
+    /// Returns the throughput of the fastest run in megabytes per second.
+    pub fn throughput(&self, bytes: usize) -> f64 {
+        let seconds = self.best.as_secs_f64();
+        if seconds == 0.0 {
+            return f64::INFINITY;
+        }
+
+        return bytes as f64 / seconds / 1_000_000.0;
+    }
+}
+
+/// Finds every match in the text using the given search function, which must return
+/// the start and end offsets of the first match in its input (if any).
+pub fn count_matches<F>(text: &str, find: F) -> usize
+    where F: Fn(&str) -> Option<(usize, usize)>
+{
+    let mut text = text;
+    let mut count = 0;
+
+    while let Some((start, end)) = find(text) {
+        count += 1;
+
+        // Continue after the match. Empty matches advance by one character.
+        let next = if end > start { end } else {
+            end + text[end..].chars().next().map_or(1, |c| c.len_utf8())
+        };
+        if next > text.len() {
+            break;
+        }
+        text = &text[next..];
+    }
+
+    return count;
+}
+
+/// Runs the given function the given number of times and measures the fastest run.
+pub fn measure<F>(iterations: usize, run: F) -> Measurement
+    where F: Fn() -> usize
+{
+    let mut best = Duration::MAX;
+    let mut matches = 0;
+
+    for _ in 0..iterations.max(1) {
+        let start = Instant::now();
+        matches = black_box(run());
+        best = best.min(start.elapsed());
+    }
+
+    return Measurement { best, matches };
+}
@@ -1,20 +1,251 @@
-use clap::Parser;
+use std::ops::Range;
+
+use clap::{ArgAction, Parser};
+
+use crate::{color::{ColorChoice, DEFAULT_PATTERN_COLORS}, text::BinaryFiles};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(author, version, about, disable_help_flag = true)]
 pub struct Args {
+    /// Print help
+    #[arg(long = "help", action = ArgAction::Help)]
+    pub help: Option<bool>,
+
     /// Use the library-supplied matcher instead of the custom one
-    #[arg(short = 'o', long = "original", default_value_t = false)]
+    #[arg(long = "original", default_value_t = false)]
     pub original: bool,
 
-    /// Return as soon as the first match is found
-    #[arg(short = 'f', long = "first", default_value_t = false)]
+    /// Return as soon as the first match is found. Long-only, since -f reads the patterns from a file
+    #[arg(long = "first", default_value_t = false)]
     pub first_only: bool,
 
+    /// Stop searching a file after the given number of selected lines (with -o, of lines with
+    /// printed matches). The context after the last one is still printed
+    #[arg(short = 'm', long = "max-count", value_name = "NUM")]
+    pub max_count: Option<usize>,
+
+    /// Treat the patterns as fixed strings instead of regular expressions
+    #[arg(short = 'F', long = "fixed-strings", default_value_t = false)]
+    pub fixed_strings: bool,
+
+    /// Only match whole words, surrounded by non-word characters
+    #[arg(short = 'w', long = "word-regexp", default_value_t = false)]
+    pub word: bool,
+
+    /// Only match whole lines
+    #[arg(short = 'x', long = "line-regexp", default_value_t = false)]
+    pub line: bool,
+
+    /// Print the line number of each line, starting from 1
+    #[arg(short = 'n', long = "line-number", default_value_t = false)]
+    pub line_number: bool,
+
+    /// Print the byte offset of each line (or match, with -o) in the file
+    #[arg(short = 'b', long = "byte-offset", default_value_t = false)]
+    pub byte_offset: bool,
+
+    /// Print only the matched parts of the lines, each on its own line
+    #[arg(short = 'o', long = "only-matching", default_value_t = false)]
+    pub only_matching: bool,
+
+    /// Print the file name before each line
+    #[arg(short = 'H', long = "with-filename", default_value_t = false)]
+    pub with_filename: bool,
+
+    /// Never print the file name before each line
+    #[arg(short = 'h', long = "no-filename", default_value_t = false, conflicts_with = "with_filename")]
+    pub no_filename: bool,
+
+    /// Select the lines that don't match instead of the ones that do
+    #[arg(short = 'v', long = "invert-match", default_value_t = false)]
+    pub invert: bool,
+
+    /// Only print the number of selected lines
+    #[arg(short = 'c', long = "count", default_value_t = false)]
+    pub count: bool,
+
+    /// Only print the name of the file if it has selected lines
+    #[arg(short = 'l', long = "files-with-matches", default_value_t = false)]
+    pub files_with_matches: bool,
+
+    /// Only print the name of the file if it has no selected lines
+    #[arg(short = 'L', long = "files-without-match", default_value_t = false)]
+    pub files_without_match: bool,
+
+    /// Print nothing, only exit with 0 if any line is selected
+    #[arg(short = 'q', long = "quiet", visible_alias = "silent", default_value_t = false)]
+    pub quiet: bool,
+
+    /// Print the given number of lines after each matching line
+    #[arg(short = 'A', long = "after-context")]
+    pub after_context: Option<usize>,
+
+    /// Print the given number of lines before each matching line
+    #[arg(short = 'B', long = "before-context")]
+    pub before_context: Option<usize>,
+
+    /// Print the given number of lines before and after each matching line
+    #[arg(short = 'C', long = "context")]
+    pub context: Option<usize>,
+
+    /// How to search binary files, which have a NUL byte near their start: only tell whether
+    /// they match, search them like text, or assume that they don't match
+    #[arg(long = "binary-files", value_name = "TYPE", value_enum, default_value_t = BinaryFiles::Binary)]
+    pub binary_files: BinaryFiles,
+
+    /// Treat the input as records ending with a NUL byte instead of lines, like the output of
+    /// `find -print0`, and end each printed line with a NUL byte too. No file is binary then.
+    /// Long-only, since -z is --search-zip
+    #[arg(long = "null-data", default_value_t = false)]
+    pub null_data: bool,
+
+    /// Treat a carriage return before a line feed as a part of the line terminator: the ends of
+    /// whole line matches (and '$' in multi-line mode) are before it, and '.' doesn't match it
+    #[arg(long = "crlf", default_value_t = false, conflicts_with = "null_data")]
+    pub crlf: bool,
+
+    /// Only search the bytes from START to END of each file, like a file of its own, except
+    /// that the anchors and word boundaries at START still see the bytes before it. Either
+    /// end may be left out. The line numbers and byte offsets are still the ones in the file
+    #[arg(long = "offset", value_name = "START:END", value_parser = parse_offset, conflicts_with_all = ["in_place", "verify"])]
+    pub offset: Option<Range<usize>>,
+
+    /// Search in compressed files too (gzip, bzip2, xz and zstd), decompressing them as they are read
+    #[arg(short = 'z', long = "search-zip", default_value_t = false)]
+    pub search_zip: bool,
+
+    /// Search the files in the given directories, and in their subdirectories
+    #[arg(short = 'r', long = "recursive", default_value_t = false)]
+    pub recursive: bool,
+
+    /// Search directories recursively, following all symbolic links
+    #[arg(short = 'R', long = "dereference-recursive", default_value_t = false)]
+    pub dereference_recursive: bool,
+
+    /// Only search the files whose names match the glob. Can be given multiple times
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip the files and directories whose names match the glob. Can be given multiple times
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Search hidden files and directories too, when searching recursively
+    #[arg(long = "hidden", default_value_t = false)]
+    pub hidden: bool,
+
+    /// Don't skip the files listed in .gitignore and .ignore files, when searching recursively
+    #[arg(long = "no-ignore", default_value_t = false)]
+    pub no_ignore: bool,
+
+    /// Highlight the matches, file names, line numbers and separators. The colors can be
+    /// configured with GREP_COLORS, like with grep
+    #[arg(long = "color", visible_alias = "colour", value_name = "WHEN", value_enum, default_value_t = ColorChoice::Auto, num_args = 0..=1, default_missing_value = "always")]
+    pub color: ColorChoice,
+
+    /// Color the matches of each pattern differently, using the given colon-separated SGR sequences in turn
+    #[arg(long = "pattern-colors", value_name = "COLORS", num_args = 0..=1, default_missing_value = DEFAULT_PATTERN_COLORS)]
+    pub pattern_colors: Option<String>,
+
+    /// Print the results as JSON Lines: a record for each selected (and context) line, with
+    /// the matches in it, surrounded by records of the start and end of each file
+    #[arg(long = "json", default_value_t = false, conflicts_with_all = ["count", "files_with_matches", "files_without_match", "tuples"])]
+    pub json: bool,
+
+    /// Print the lines (or with -o, the matches) with each match replaced by the template, where $0
+    /// is the whole match, and $1 or $name are its groups. If given multiple times, each pattern
+    /// is replaced by its own template, in the order of the patterns. Long-only, since -r is --recursive
+    #[arg(long = "replace", value_name = "TEMPLATE", conflicts_with_all = ["json", "tuples"])]
+    pub replacements: Vec<String>,
+
+    /// Replace the matches in the files themselves, instead of printing the replaced lines. Symbolic
+    /// links are followed, and the files they point to are replaced. Binary files are left alone,
+    /// unless they are searched like text with --binary-files=text
+    #[arg(long = "in-place", default_value_t = false, requires = "replacements", conflicts_with_all = ["invert", "only_matching", "count", "files_with_matches", "files_without_match", "search_zip", "max_count", "first_only"])]
+    pub in_place: bool,
+
+    /// Truncate the printed lines (or with -o, matches) longer than the given number of bytes,
+    /// and mark where they were cut. The JSON records are never truncated
+    #[arg(long = "max-columns", value_name = "NUM")]
+    pub max_columns: Option<usize>,
+
+    /// Print the (start, end) byte offsets of each match instead of the lines
+    #[arg(long = "tuples", default_value_t = false)]
+    pub tuples: bool,
+
+    /// Print the time it took to compile the patterns and to search the files, the number of
+    /// searched bytes, and the strategy and candidate counters of the matcher to the standard error
+    #[arg(long = "stats", default_value_t = false)]
+    pub stats: bool,
+
+    /// Compare the matches with the ones of the library-supplied matcher instead of searching,
+    /// and print every match that differs, with its pattern and the heuristic chosen for it
+    #[arg(long = "verify", default_value_t = false, conflicts_with = "original")]
+    pub verify: bool,
+
+    /// Print the matching strategy chosen for the pattern(s) instead of searching
+    #[arg(long = "explain", default_value_t = false)]
+    pub explain: bool,
+
     /// The pattern(s) to search for. To use multiple patterns, include the flag multiple times
-    #[arg(short = 'p', long = "pattern")]
+    #[arg(short = 'p', long = "pattern", visible_short_alias = 'e', visible_alias = "regexp")]
     pub patterns: Vec<String>,
 
-    /// The file to search the pattern(s) in
-    pub file: String
+    /// Read the patterns from the file, one per line ('-' for the standard input). Can be given multiple times
+    #[arg(short = 'f', long = "file", value_name = "FILE")]
+    pub pattern_files: Vec<String>,
+
+    /// Skip the lines of the pattern files starting with the given prefix, like '#'
+    #[arg(long = "comment-prefix", value_name = "PREFIX")]
+    pub comment_prefix: Option<String>,
+
+    /// The files (or, with -r, directories) to search the pattern(s) in. Without any, or with '-',
+    /// the standard input is searched (or with -r, the current directory)
+    pub paths: Vec<String>
+}
+
+impl Args {
+    /// Determines whether directories are searched recursively.
+    pub fn recursive(&self) -> bool {
+        return self.recursive || self.dereference_recursive;
+    }
+
+    /// Returns the byte ending each line (or record) of the searched files.
+    pub fn terminator(&self) -> u8 {
+        return if self.null_data { b'\0' } else { b'\n' };
+    }
+
+    /// Determines whether the line numbers are needed, which they are in JSON records too.
+    pub fn line_numbers(&self) -> bool {
+        return self.line_number || self.json;
+    }
+
+    /// Determines whether the file names are printed before the lines. Unless chosen
+    /// explicitly, they are printed whenever more than one file may be searched.
+    pub fn filenames(&self) -> bool {
+        if self.with_filename || self.no_filename {
+            return self.with_filename;
+        }
+
+        return self.recursive() || self.paths.len() > 1;
+    }
+}
+
+/// Parses a range of byte offsets, given as START:END. A missing start is the start
+/// of the file, and a missing end is the end of it.
+fn parse_offset(value: &str) -> Result<Range<usize>, String> {
+    let (start, end) = match value.split_once(':') {
+        Some(parts) => parts,
+        None => return Err(String::from("expected START:END"))
+    };
+
+    let parse = |part: &str, default: usize| {
+        return if part.is_empty() { Ok(default) } else { part.parse::<usize>().map_err(|error| format!("{}: {}", part, error)) };
+    };
+    let (start, end) = (parse(start, 0)?, parse(end, usize::MAX)?);
+
+    if start > end {
+        return Err(String::from("the start is after the end"));
+    }
+    return Ok(start..end);
 }
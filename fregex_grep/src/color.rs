@@ -0,0 +1,152 @@
+use std::{env, io::{stdout, IsTerminal}};
+
+use clap::ValueEnum;
+
+use crate::args::Args;
+
+/// The colors of the patterns, if they are colored separately without being given explicitly.
+pub const DEFAULT_PATTERN_COLORS: &str = "01;31:01;32:01;33:01;34:01;35:01;36";
+
+/// When the output is colored.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    /// Only if the output is a terminal, and colors were not disabled by `NO_COLOR`.
+    Auto,
+    /// Always.
+    Always,
+    /// Never.
+    Never,
+}
+
+/// The SGR sequences used to color the parts of the output, as configured by `GREP_COLORS`.
+#[derive(Clone, Debug)]
+pub struct Colors {
+    /// The color of the matches in selected lines.
+    pub selected_match: String,
+    /// The color of the matches in context lines.
+    pub context_match: String,
+    /// The color of the whole selected lines.
+    pub selected_line: String,
+    /// The color of the whole context lines.
+    pub context_line: String,
+    /// The color of the file names.
+    pub filename: String,
+    /// The color of the line numbers.
+    pub line_number: String,
+    /// The color of the byte offsets.
+    pub byte_offset: String,
+    /// The color of the separators.
+    pub separator: String,
+    /// Whether the colors of the selected and context lines are swapped with inverted matching.
+    pub reverse: bool,
+    /// Whether the line is not cleared to its end after each colored part.
+    pub no_erase: bool,
+    /// The colors of the matches of each pattern, used in turn. If empty, every
+    /// match is colored the same.
+    pub patterns: Vec<String>,
+}
+
+impl Default for Colors {
+    /// Creates the default colors of grep.
+    fn default() -> Colors {
+        return Colors {
+            selected_match: String::from("01;31"),
+            context_match: String::from("01;31"),
+            selected_line: String::new(),
+            context_line: String::new(),
+            filename: String::from("35"),
+            line_number: String::from("32"),
+            byte_offset: String::from("32"),
+            separator: String::from("36"),
+            reverse: false,
+            no_erase: false,
+            patterns: vec![],
+        };
+    }
+}
+
+impl Colors {
+    /// Parses a `GREP_COLORS`-style configuration: a colon-separated list of `key=SGR`
+    /// capabilities (like `ms=01;31:fn=35`) and boolean ones (like `ne`), applied over
+    /// the default colors. Unknown capabilities are ignored, like grep does.
+    pub fn parse(config: &str) -> Colors {
+        let mut colors = Colors::default();
+
+        for capability in config.split(':') {
+            let (key, value) = match capability.split_once('=') {
+                Some((key, value)) => (key, value.to_string()),
+                None => (capability, String::new())
+            };
+
+            match key {
+                "mt" => { colors.selected_match = value.clone(); colors.context_match = value; },
+                "ms" => colors.selected_match = value,
+                "mc" => colors.context_match = value,
+                "sl" => colors.selected_line = value,
+                "cx" => colors.context_line = value,
+                "fn" => colors.filename = value,
+                "ln" => colors.line_number = value,
+                "bn" => colors.byte_offset = value,
+                "se" => colors.separator = value,
+                "rv" => colors.reverse = true,
+                "ne" => colors.no_erase = true,
+                _ => {}
+            }
+        }
+
+        return colors;
+    }
+
+    /// Returns the colors chosen by the arguments and the environment, or None if the output isn't colored.
+    pub fn from_args(args: &Args) -> Option<Colors> {
+        let enabled = match args.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && env::var_os("TERM").is_none_or(|term| term != "dumb")
+            }
+        };
+        if !enabled {
+            return None;
+        }
+
+        let mut colors = Colors::parse(&env::var("GREP_COLORS").unwrap_or_default());
+        if let Some(patterns) = &args.pattern_colors {
+            colors.patterns = patterns.split(':').map(|color| color.to_string()).collect();
+        }
+
+        return Some(colors);
+    }
+
+    /// Returns the color of a match of the given pattern, in a selected or a context line.
+    pub fn match_color(&self, pattern: usize, context: bool) -> &str {
+        if !self.patterns.is_empty() {
+            return &self.patterns[pattern % self.patterns.len()];
+        }
+
+        return if context { &self.context_match } else { &self.selected_match };
+    }
+
+    /// Determines whether the matches in selected or context lines are highlighted.
+    pub fn highlights(&self, context: bool) -> bool {
+        return !self.patterns.is_empty() || !self.match_color(0, context).is_empty();
+    }
+
+    /// Returns the color of a whole selected or context line. With inverted matching,
+    /// the colors may be swapped.
+    pub fn line_color(&self, context: bool, invert: bool) -> &str {
+        return if context != (self.reverse && invert) { &self.context_line } else { &self.selected_line };
+    }
+
+    /// Returns the sequence starting the given color.
+    pub fn start(&self, color: &str) -> String {
+        return format!("\x1b[{}m{}", color, if self.no_erase { "" } else { "\x1b[K" });
+    }
+
+    /// Returns the sequence ending a color.
+    pub fn end(&self) -> &'static str {
+        return if self.no_erase { "\x1b[m" } else { "\x1b[m\x1b[K" };
+    }
+}
@@ -0,0 +1,144 @@
+use std::io::{self, Cursor, Read};
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// The number of bytes at the start of a file that identify its compression format.
+const MAGIC_SIZE: usize = 6;
+
+/// The compression formats of the files that can be searched in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression format from the first bytes of a file, or returns None
+    /// if the file isn't compressed in any of the known formats.
+    pub fn detect(magic: &[u8]) -> Option<Compression> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            return Some(Compression::Gzip);
+        }
+        if magic.starts_with(b"BZh") {
+            return Some(Compression::Bzip2);
+        }
+        if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            return Some(Compression::Xz);
+        }
+        if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            return Some(Compression::Zstd);
+        }
+
+        return None;
+    }
+
+    /// Wraps the reader of a compressed file into one reading its decompressed contents.
+    /// Files consisting of multiple concatenated parts are decompressed whole.
+    pub fn decoder(self, reader: impl Read + 'static) -> io::Result<Box<dyn Read>> {
+        return Ok(match self {
+            Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Compression::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+            Compression::Zstd => Box::new(ZstdDecoder::new(reader)?),
+        });
+    }
+}
+
+/// Reads the first bytes of a file, which identify its compression format. Shorter
+/// files are read whole.
+pub fn read_magic(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut magic = Vec::with_capacity(MAGIC_SIZE);
+    reader.take(MAGIC_SIZE as u64).read_to_end(&mut magic)?;
+    return Ok(magic);
+}
+
+/// Detects whether a stream is compressed, and if it is, returns a stream of its
+/// decompressed contents. Otherwise, the stream is returned as it was.
+pub fn decompressed(mut reader: Box<dyn Read>) -> io::Result<Box<dyn Read>> {
+    let magic = read_magic(&mut reader)?;
+    let compression = Compression::detect(&magic);
+
+    // The bytes read for the detection are put back in front of the rest of the stream.
+    let reader = Box::new(Cursor::new(magic).chain(reader));
+    return match compression {
+        Some(compression) => compression.decoder(reader),
+        None => Ok(reader)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bzip2::read::BzEncoder;
+    use flate2::read::GzEncoder;
+    use xz2::read::XzEncoder;
+
+    use super::*;
+
+    /// The formats every test is repeated with.
+    const FORMATS: [Compression; 4] = [Compression::Gzip, Compression::Bzip2, Compression::Xz, Compression::Zstd];
+
+    /// Compresses the given bytes in the given format.
+    fn compress(compression: Compression, bytes: &[u8]) -> Vec<u8> {
+        let mut reader: Box<dyn Read + '_> = match compression {
+            Compression::Gzip => Box::new(GzEncoder::new(bytes, flate2::Compression::default())),
+            Compression::Bzip2 => Box::new(BzEncoder::new(bytes, bzip2::Compression::default())),
+            Compression::Xz => Box::new(XzEncoder::new(bytes, 6)),
+            Compression::Zstd => return zstd::encode_all(bytes, 0).unwrap(),
+        };
+
+        let mut compressed = vec![];
+        reader.read_to_end(&mut compressed).unwrap();
+        return compressed;
+    }
+
+    /// Reads the whole stream given by `decompressed`.
+    fn read(bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+        let mut contents = vec![];
+        decompressed(Box::new(Cursor::new(bytes)))?.read_to_end(&mut contents)?;
+        return Ok(contents);
+    }
+
+    /// Test that the formats are detected from the start of the compressed files.
+    #[test]
+    fn test_detect() {
+        for compression in FORMATS {
+            assert_eq!(Some(compression), Compression::detect(&compress(compression, b"foo\n")[..MAGIC_SIZE]));
+        }
+
+        assert_eq!(None, Compression::detect(b"foo\nbar\n"));
+        assert_eq!(None, Compression::detect(b"BZ"));
+        assert_eq!(None, Compression::detect(b""));
+    }
+
+    /// Test that the compressed streams are decompressed whole, even if they consist of
+    /// multiple parts, while other streams are left as they are.
+    #[test]
+    fn test_decompressed() {
+        for compression in FORMATS {
+            let mut compressed = compress(compression, b"foo\nbar\n");
+            assert_eq!(b"foo\nbar\n", read(compressed.clone()).unwrap().as_slice(), "Unexpected contents with {:?}", compression);
+
+            compressed.extend(compress(compression, b"baz\n"));
+            assert_eq!(b"foo\nbar\nbaz\n", read(compressed).unwrap().as_slice(), "Unexpected parts with {:?}", compression);
+        }
+
+        assert_eq!(b"foo\nbar\n", read(b"foo\nbar\n".to_vec()).unwrap().as_slice());
+        assert_eq!(b"BZ", read(b"BZ".to_vec()).unwrap().as_slice());
+    }
+
+    /// Test that the corrupt compressed streams can't be read.
+    #[test]
+    fn test_corrupt() {
+        for compression in FORMATS {
+            let mut compressed = compress(compression, b"foo\nbar\n");
+            let middle = compressed.len() / 2;
+            compressed.truncate(middle);
+            assert!(read(compressed).is_err(), "Expected an error with {:?}", compression);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{path::Path, process::exit};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{overrides::{Override, OverrideBuilder}, WalkBuilder};
+
+use crate::args::Args;
+
+/// Builds the overrides skipping the files and directories of the exclude globs,
+/// relative to the given root. Exits if any of the globs is invalid.
+fn overrides(args: &Args, root: &Path) -> Override {
+    let mut builder = OverrideBuilder::new(root);
+
+    // Only negated globs are added, since any other glob would whitelist the matching
+    // files, even if they are ignored otherwise.
+    for glob in &args.exclude {
+        if let Err(error) = builder.add(&format!("!{}", glob)) {
+            eprintln!("Invalid glob: {}", error); exit(2);
+        }
+    }
+
+    match builder.build() {
+        Ok(overrides) => return overrides,
+        Err(error) => { eprintln!("Invalid glob: {}", error); exit(2); }
+    }
+}
+
+/// Builds the set of the include globs, which the names of the walked files must match,
+/// or None, if every file is included. Exits if any of the globs is invalid.
+fn includes(args: &Args) -> Option<GlobSet> {
+    if args.include.is_empty() {
+        return None;
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in &args.include {
+        match Glob::new(glob) {
+            Ok(glob) => { builder.add(glob); },
+            Err(error) => { eprintln!("Invalid glob: {}", error); exit(2); }
+        }
+    }
+
+    match builder.build() {
+        Ok(includes) => return Some(includes),
+        Err(error) => { eprintln!("Invalid glob: {}", error); exit(2); }
+    }
+}
+
+/// Calls the given function with the path of every file to search: the given paths
+/// themselves, and with recursion, the files in the given directories. The files are
+/// visited until the function returns false. Returns whether any error occurred while
+/// listing the files; the errors themselves are reported as they occur.
+pub fn for_each_file<F>(args: &Args, mut function: F) -> bool
+where F: FnMut(&Path) -> bool {
+    let mut error = false;
+    let includes = includes(args);
+
+    // Without any paths, the standard input is searched, or with recursion, the current directory.
+    let implicit = args.paths.is_empty();
+    let paths = if !implicit {
+        args.paths.clone()
+    } else if args.recursive() {
+        vec![String::from(".")]
+    } else {
+        vec![String::from("-")]
+    };
+
+    for path in &paths {
+        let path = Path::new(path);
+
+        // Without recursion, the paths are searched as they are. The standard input is never walked.
+        if !args.recursive() || path.as_os_str() == "-" {
+            if path.is_dir() {
+                eprintln!("{}: Is a directory", path.display());
+                error = true;
+                continue;
+            }
+            if !function(path) {
+                return error;
+            }
+            continue;
+        }
+
+        // Hidden and ignored files are skipped, and symbolic links are only
+        // followed if requested (or if they were given explicitly).
+        let walk = WalkBuilder::new(path)
+            .hidden(!args.hidden)
+            .ignore(!args.no_ignore)
+            .git_ignore(!args.no_ignore)
+            .git_global(!args.no_ignore)
+            .git_exclude(!args.no_ignore)
+            .parents(!args.no_ignore)
+            .require_git(false)
+            .follow_links(args.dereference_recursive)
+            .overrides(overrides(args, path))
+            .build();
+
+        for entry in walk {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(walk_error) => { eprintln!("{}", walk_error); error = true; continue; }
+            };
+
+            // Only the files are searched, directories are only walked through.
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            // The included files are only narrowed down from the ones that are not skipped anyway.
+            if includes.as_ref().is_some_and(|includes| !includes.is_match(entry.file_name())) {
+                continue;
+            }
+            // The implicit current directory is not part of the printed paths.
+            let path = if implicit { entry.path().strip_prefix(".").unwrap_or(entry.path()) } else { entry.path() };
+            if !function(path) {
+                return error;
+            }
+        }
+    }
+
+    return error;
+}
@@ -0,0 +1,94 @@
+use std::{io::{Result, Write}, str::from_utf8};
+
+/// The characters of the base64 encoding.
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes the bytes in base64, with padding.
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        // Every 3 bytes are split into 4 groups of 6 bits, missing bytes are padded.
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| group | ((*byte as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[((group >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    return encoded;
+}
+
+/// Writes a string as a JSON string literal, escaping the characters that can't appear in one.
+pub fn write_str(out: &mut impl Write, string: &str) -> Result<()> {
+    out.write_all(b"\"")?;
+
+    for c in string.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+
+    return out.write_all(b"\"");
+}
+
+/// Writes arbitrary bytes as an object with a single field: `text` with the string itself,
+/// if the bytes are valid UTF-8, or `bytes` with their base64 encoding otherwise.
+pub fn write_data(out: &mut impl Write, bytes: &[u8]) -> Result<()> {
+    match from_utf8(bytes) {
+        Ok(text) => {
+            out.write_all(b"{\"text\":")?;
+            write_str(out, text)?;
+        },
+        Err(_) => write!(out, "{{\"bytes\":\"{}\"", base64(bytes))?
+    }
+
+    return out.write_all(b"}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns what the given function writes, as a string.
+    fn written(write: impl Fn(&mut Vec<u8>) -> Result<()>) -> String {
+        let mut out = vec![];
+        write(&mut out).unwrap();
+        return String::from_utf8(out).unwrap();
+    }
+
+    /// Test that the bytes are encoded in base64, with padding.
+    #[test]
+    fn test_base64() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYg==", base64(b"foob"));
+        assert_eq!("/w==", base64(b"\xff"));
+    }
+
+    /// Test that the characters that can't appear in a JSON string are escaped.
+    #[test]
+    fn test_write_str() {
+        assert_eq!(r#""a\"b\\c""#, written(|out| write_str(out, "a\"b\\c")));
+        assert_eq!(r#""\n\r\t\u0000\u001f""#, written(|out| write_str(out, "\n\r\t\0\x1f")));
+        assert_eq!("\"é€\"", written(|out| write_str(out, "é€")));
+    }
+
+    /// Test that valid UTF-8 is written as text, and anything else as base64.
+    #[test]
+    fn test_write_data() {
+        assert_eq!(r#"{"text":"foo"}"#, written(|out| write_data(out, b"foo")));
+        assert_eq!(r#"{"bytes":"Zm9v/w=="}"#, written(|out| write_data(out, b"foo\xff")));
+    }
+}
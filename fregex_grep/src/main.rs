@@ -1,96 +1,259 @@
 use clap::Parser;
-use fregex::{Regex, MultiRegex, RegexMatcher};
-use regex::Regex as OriginalRegex;
-use text::{read_text, read_text_mmap};
-use std::{process::exit, str::from_utf8_unchecked};
+use fregex::{RegexBuilder, RegexMatcher};
+use regex::{Regex as OriginalRegex, RegexBuilder as OriginalRegexBuilder};
+use std::{io::ErrorKind, path::Path, process::exit, time::{Duration, Instant}};
 
-use crate::args::Args;
+use crate::{args::Args, output::Printer, replace::Replacer, search::{Engine, SearchError}, text::{Input, Text}, verify::Verifier};
 
 mod args;
+mod color;
+mod decompress;
+mod files;
+mod json;
+mod output;
+mod replace;
+mod search;
 mod text;
+mod verify;
+
+fn compile_fregex<'p>(args: &Args, patterns: &'p [&'p str]) -> Box<dyn RegexMatcher + 'p> {
+    // The output is line-oriented, so the anchors match at the start and end of every line.
+    let mut builder = RegexBuilder::new();
+    builder.whole_word(args.word).whole_line(args.line).fixed_strings(args.fixed_strings);
+    builder.line_terminator(args.terminator()).crlf(args.crlf).multi_line(true).stats(args.stats);
 
-fn run_fregex(args: &Args, patterns: &[&str]) {
     // Create proper matcher.
-    let matcher: Box<dyn RegexMatcher> = if args.patterns.len() > 1 {
-        match MultiRegex::new(&patterns) {
+    let matcher: Box<dyn RegexMatcher + 'p> = if patterns.len() > 1 {
+        match builder.build_multi(&patterns) {
             Ok(matcher) => Box::new(matcher),
-            Err(_) => { println!("Error parsing patterns!"); exit(1); }
+            Err(_) => { eprintln!("Error parsing patterns!"); exit(2); }
         }
     } else {
-        match Regex::new(patterns[0]) {
+        match builder.build(patterns[0]) {
             Ok(matcher) => Box::new(matcher),
-            Err(_) => { println!("Error parsing pattern!"); exit(1); }
+            Err(_) => { eprintln!("Error parsing pattern!"); exit(2); }
         }
     };
 
+    return matcher;
+}
 
-    // Read text in whole and find every single match.
-    let string = read_text_mmap(&args.file);
-    let mut text: &str = unsafe { from_utf8_unchecked(&string[..]) };
-    let mut offset = 0;
+/// Returns the single pattern the library-supplied matcher searches for.
+fn original_pattern(args: &Args, patterns: &[&str]) -> String {
+    // Multiple patterns are joined into a single alternation.
+    // Fixed strings are escaped, so they only match themselves.
+    let pattern = patterns
+        .iter()
+        .map(|p| if args.fixed_strings { regex::escape(p) } else { p.to_string() })
+        .map(|p| format!("(?:{})", p))
+        .collect::<Vec<String>>()
+        .join("|");
 
-    while text.len() > 0 {
-        if let Some(result) = matcher.find(&text) {
-            let start = result.start() + offset;
-            let end = result.end() + offset;
+    // The boundaries are added to the pattern itself.
+    return if args.line {
+        format!("(?m:^)(?:{})(?m:$)", pattern)
+    } else if args.word {
+        format!(r"\b{{start-half}}(?:{})\b{{end-half}}", pattern)
+    } else {
+        pattern
+    };
+}
 
-            text = &text[result.end()..];
-            offset += result.end();
+fn compile_original(args: &Args, patterns: &[&str]) -> OriginalRegex {
+    let pattern = original_pattern(args, patterns);
 
-            println!("({}, {})", start, end);
+    // The anchors match at every line, like the ones of the custom matcher.
+    match OriginalRegexBuilder::new(&pattern).line_terminator(args.terminator()).crlf(args.crlf).multi_line(true).build() {
+        Ok(matcher) => return matcher,
+        Err(_) => { eprintln!("Error parsing patterns!"); exit(2); }
+    }
+}
 
-            // If we only need the first match, break out of the loop here.
-            if args.first_only {
-                break;
-            }
-        } else {
-            break;
-        }
+fn run_explain(args: &Args, patterns: &[&str]) {
+    let matcher = compile_fregex(args, patterns);
+    print!("{}", matcher.explain());
+}
+
+/// Returns the name of a file, as printed in the results.
+fn display_name(path: &Path) -> String {
+    if path.as_os_str() == "-" {
+        return String::from("(standard input)");
     }
+
+    return path.to_string_lossy().to_string();
 }
 
-fn run_original(args: &Args, patterns: &[&str]) {
-    let matcher = match OriginalRegex::new(patterns[0]) {
-        Ok(matcher) => Box::new(matcher),
-        Err(_) => { println!("Error parsing patterns!"); exit(1); }
+/// Compares the matches of both matchers in every file, and prints the ones that differ.
+/// Returns whether any of them differ, and whether any error occurred.
+fn run_verify(args: &Args, patterns: &[&str]) -> (bool, bool) {
+    let originals = patterns.iter().map(|pattern| compile_original(args, &[*pattern])).collect();
+    let verifier = Verifier::new(compile_fregex(args, patterns), compile_original(args, patterns), originals, args.terminator());
+    let (mut files, mut matches, mut mismatches) = (0, 0, 0);
+    let mut failed = false;
+
+    let walk_failed = files::for_each_file(args, |path| {
+        let name = display_name(path);
+        match text::read(path, args.search_zip) {
+            Ok(contents) => {
+                let (found, differing) = verifier.verify(&Text::new(&contents, args.terminator()), &name);
+                files += 1;
+                matches += found;
+                mismatches += differing;
+            },
+            Err(error) => { eprintln!("{}: {}", name, error); failed = true; }
+        }
+
+        return true;
+    });
+
+    println!("Verified {} files with {} matches: {} mismatches", files, matches, mismatches);
+    return (mismatches > 0, failed || walk_failed);
+}
+
+/// Searches a single file, and prints the results. If the matches may span multiple lines,
+/// streams are read whole. Returns whether any line was selected, or None if the file
+/// couldn't be read.
+fn search_file<'a>(args: &'a Args, engine: &Engine, multi_line: bool, path: &Path, printer: &mut Printer<'a>) -> Option<bool> {
+    let name = display_name(path);
+
+    // Whole files are searched at once, while streams are searched as they are read. To search
+    // only a range of a stream, it is read whole, since the text before the range is needed too,
+    // just like the lines after the end of a read part, if a match may continue in them.
+    let input = if args.offset.is_some() || multi_line {
+        text::read(path, args.search_zip).map(Input::Whole)
+    } else {
+        text::open(path, args.search_zip)
+    };
+    let result = match input {
+        Ok(Input::Whole(contents)) => search::search(engine, &contents, &name, args, printer).map_err(SearchError::Write),
+        Ok(Input::Stream(reader)) => search::search_stream(engine, reader, &name, args, printer),
+        Err(error) => Err(SearchError::Read(error))
     };
 
-    // Read text in whole and find every single match.
-    let string = read_text_mmap(&args.file);
-    let mut text: &str = unsafe { from_utf8_unchecked(&string[..]) };
-    let mut offset = 0;
+    // If the output was closed (for example, by a pipe), there's nothing left to do.
+    match result {
+        Ok(selected) => return Some(selected),
+        Err(SearchError::Read(error)) => { eprintln!("{}: {}", name, error); return None; },
+        Err(SearchError::Write(error)) if error.kind() == ErrorKind::BrokenPipe => exit(2),
+        Err(SearchError::Write(error)) => { eprintln!("Error writing output: {}", error); exit(2); }
+    }
+}
 
-    while text.len() > 0 {
-        if let Some(result) = matcher.find(&text) {
-            let start = result.start() + offset;
-            let end = result.end() + offset;
+/// Replaces the matches in a single file, and writes the result back into it. Returns
+/// whether anything was replaced, or None if the file couldn't be replaced.
+fn replace_file(args: &Args, engine: &Engine, replacer: &Replacer, path: &Path) -> Option<bool> {
+    match replace::in_place(engine, replacer, path, args) {
+        Ok(replaced) => return Some(replaced),
+        Err(error) => { eprintln!("{}: {}", path.display(), error); return None; }
+    }
+}
 
-            text = &text[result.end()..];
-            offset += result.end();
+/// Searches every file with the same engine (or with replacing in place, replaces the
+/// matches in them). Returns whether any line was selected, whether any error occurred,
+/// and the number of searched bytes.
+fn run_search(args: &Args, engine: &Engine, multi_line: bool, replacer: Option<&Replacer>) -> (bool, bool, usize) {
+    let mut printer = Printer::new(args, replacer);
+    let mut selected = false;
+    let mut failed = false;
 
-            println!("({}, {})", start, end);
+    let walk_failed = files::for_each_file(args, |path| {
+        let result = match replacer {
+            Some(replacer) if args.in_place => replace_file(args, engine, replacer, path),
+            _ => search_file(args, engine, multi_line, path, &mut printer)
+        };
 
-            // If we only need the first match, break out of the loop here.
-            if args.first_only {
-                break;
-            }
-        } else {
-            break;
+        match result {
+            Some(found) => selected |= found,
+            None => failed = true
         }
+
+        // When quiet, a single selected line decides the outcome.
+        return !(args.quiet && selected);
+    });
+
+    if printer.summary().and_then(|_| printer.flush()).is_err() {
+        exit(2);
+    }
+    return (selected, failed || walk_failed, printer.scanned());
+}
+
+/// Prints the statistics of a search to the standard error. The strategy and the counters
+/// of the candidates are only known for the custom matcher.
+fn print_stats(engine: &Engine, compile_time: Duration, search_time: Duration, scanned: usize) {
+    let throughput = scanned as f64 / search_time.as_secs_f64() / 1_000_000.0;
+
+    eprintln!("compile time: {:.3} ms", compile_time.as_secs_f64() * 1000.0);
+    eprintln!("search time: {:.3} ms", search_time.as_secs_f64() * 1000.0);
+    eprintln!("bytes scanned: {}", scanned);
+    eprintln!("throughput: {:.1} MB/s", throughput);
+
+    if let Engine::Fregex(matcher) = engine {
+        eprint!("{}", matcher.explain());
+        eprint!("{}", matcher.stats());
     }
 }
 
+/// Collects the patterns given as arguments, and the ones read from the pattern files.
+fn load_patterns(args: &Args) -> Vec<String> {
+    let mut patterns = args.patterns.clone();
+
+    for path in &args.pattern_files {
+        match text::read_patterns(path, args.comment_prefix.as_deref()) {
+            Ok(read) => patterns.extend(read),
+            Err(error) => { eprintln!("{}: {}", path, error); exit(2); }
+        }
+    }
+
+    return patterns;
+}
+
 fn main() {
     // Parse arguments.
     let args = Args::parse();
-    if args.patterns.len() == 0 {
-        println!("No patterns were supplied!"); exit(1);
+    let patterns = load_patterns(&args);
+    if patterns.len() == 0 {
+        eprintln!("No patterns were supplied!"); exit(2);
     }
-    let patterns: Vec<&str> = args.patterns.iter().map(|p| p.as_str()).collect();
+    let patterns: Vec<&str> = patterns.iter().map(|p| p.as_str()).collect();
 
-    if args.original {
-        run_original(&args, &patterns);
+    if args.explain {
+        run_explain(&args, &patterns);
+        return;
+    }
+
+    // Like diff, exit with 0 if the matches are the same, with 1 if they differ, and with 2 on errors.
+    if args.verify {
+        let (differ, failed) = run_verify(&args, &patterns);
+        exit(if failed { 2 } else if differ { 1 } else { 0 });
+    }
+
+    let replacer = if args.replacements.is_empty() {
+        None
+    } else {
+        match Replacer::new(&args, &patterns) {
+            Ok(replacer) => Some(replacer),
+            Err(error) => { eprintln!("{}", error); exit(2); }
+        }
+    };
+
+    let started = Instant::now();
+    let engine = if args.original {
+        Engine::Original(compile_original(&args, &patterns))
     } else {
-        run_fregex(&args, &patterns);
+        Engine::Fregex(compile_fregex(&args, &patterns))
+    };
+    let compile_time = started.elapsed();
+
+    let multi_line = search::crosses_lines(&original_pattern(&args, &patterns), &args);
+    let (selected, failed, scanned) = run_search(&args, &engine, multi_line, replacer.as_ref());
+    if args.stats {
+        print_stats(&engine, compile_time, started.elapsed() - compile_time, scanned);
+    }
+
+    // Like grep, exit with 0 if any line was selected, and with 1 otherwise. Errors
+    // take precedence, unless a line was selected while being quiet.
+    if failed && !(args.quiet && selected) {
+        exit(2);
     }
+    exit(if selected { 0 } else { 1 });
 }
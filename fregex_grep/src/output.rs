@@ -0,0 +1,335 @@
+use std::{cmp::min, io::{stdout, BufWriter, Result, StdoutLock, Write}, ops::Range};
+
+use crate::{args::Args, color::Colors, json, replace::Replacer};
+
+/// Writes a part of the output in the given color, or without any color if it is empty.
+fn paint(out: &mut impl Write, colors: &Colors, color: &str, content: &[u8]) -> Result<()> {
+    if color.is_empty() || content.is_empty() {
+        return out.write_all(content);
+    }
+
+    write!(out, "{}", colors.start(color))?;
+    out.write_all(content)?;
+    return write!(out, "{}", colors.end());
+}
+
+/// The numbers of the results printed as JSON records.
+#[derive(Clone, Copy, Debug, Default)]
+struct Counts {
+    /// The number of searched files.
+    files: usize,
+    /// The number of files with selected lines.
+    files_matched: usize,
+    /// The number of selected lines.
+    lines: usize,
+    /// The number of matches in the selected lines.
+    matches: usize,
+}
+
+/// Writes the results of the search to the standard output, in the format chosen by the arguments.
+pub struct Printer<'a> {
+    /// The buffered standard output.
+    out: BufWriter<StdoutLock<'static>>,
+    /// The arguments deciding the format of the output.
+    args: &'a Args,
+    /// The colors of the output, if it is colored.
+    colors: Option<Colors>,
+    /// The replacer of the matches in the selected lines, if they are replaced.
+    replacer: Option<&'a Replacer>,
+    /// The numbers of the results in the current file, only counted for the JSON records.
+    counts: Counts,
+    /// The numbers of the results in every file, only counted for the JSON records.
+    totals: Counts,
+    /// The number of bytes searched in every file.
+    scanned: usize,
+    /// The offset of the last selected line printed as a JSON record, so that
+    /// the lines with multiple printed matches are only counted once.
+    last_line: Option<usize>,
+}
+
+impl<'a> Printer<'a> {
+    /// Creates a new printer writing to the standard output.
+    pub fn new(args: &'a Args, replacer: Option<&'a Replacer>) -> Printer<'a> {
+        return Printer {
+            out: BufWriter::new(stdout().lock()),
+            args,
+            colors: Colors::from_args(args),
+            replacer,
+            counts: Counts::default(),
+            totals: Counts::default(),
+            scanned: 0,
+            last_line: None,
+        };
+    }
+
+    /// Determines whether the output is JSON records.
+    pub fn json(&self) -> bool {
+        return self.args.json;
+    }
+
+    /// Determines whether the matches within the printed lines are needed: to highlight
+    /// them if the output is colored, to list them in the JSON records, or to replace them.
+    pub fn highlights(&self) -> bool {
+        return self.colors.is_some() || self.json() || self.replacer.is_some();
+    }
+
+    /// Writes a part of the output in the color chosen by the given function, if the output is colored.
+    fn write(&mut self, color: fn(&Colors) -> &str, content: &[u8]) -> Result<()> {
+        return match &self.colors {
+            Some(colors) => paint(&mut self.out, colors, color(colors), content),
+            None => self.out.write_all(content)
+        };
+    }
+
+    /// Prints a line (or a part of one) of the given file. The line number is only
+    /// printed if it is given, and the byte offset only if it was requested. The
+    /// prefixes are separated by ':' for matching lines, and by '-' for context lines.
+    /// If the output is colored, the given matches of the patterns are highlighted. If they
+    /// are replaced, the replacements are printed (and highlighted) in selected lines instead.
+    /// Lines longer than the maximum number of columns are truncated. Each line is ended
+    /// with the terminator of the searched lines.
+    pub fn line(&mut self, path: &str, number: Option<usize>, offset: usize, content: &[u8], matches: &[(Range<usize>, usize)], context: bool) -> Result<()> {
+        if self.json() {
+            return self.json_line(path, number, offset, content, matches, context);
+        }
+
+        let replaced;
+        let (content, matches) = match self.replacer {
+            Some(replacer) if !context => {
+                replaced = replacer.replace(content, matches);
+                (&replaced.0[..], &replaced.1[..])
+            },
+            _ => (content, matches)
+        };
+
+        // Lines longer than the maximum are cut at a character boundary, and the parts
+        // of the matches after the cut are left out.
+        let clipped: Vec<(Range<usize>, usize)>;
+        let (content, matches, truncated) = match self.args.max_columns {
+            Some(max) if content.len() > max => {
+                let mut cut = max;
+                while cut > 0 && content[cut] & 0xC0 == 0x80 {
+                    cut -= 1;
+                }
+                clipped = matches
+                    .iter()
+                    .filter(|(found, _)| found.start < cut)
+                    .map(|(found, pattern)| (found.start..min(found.end, cut), *pattern))
+                    .collect();
+                (&content[..cut], &clipped[..], true)
+            },
+            _ => (content, matches, false)
+        };
+
+        let separator: &[u8] = if context { b"-" } else { b":" };
+
+        if self.args.filenames() {
+            self.write(|c| &c.filename, path.as_bytes())?;
+            self.write(|c| &c.separator, separator)?;
+        }
+        if let Some(number) = number {
+            self.write(|c| &c.line_number, number.to_string().as_bytes())?;
+            self.write(|c| &c.separator, separator)?;
+        }
+        if self.args.byte_offset {
+            self.write(|c| &c.byte_offset, offset.to_string().as_bytes())?;
+            self.write(|c| &c.separator, separator)?;
+        }
+
+        match &self.colors {
+            Some(colors) => {
+                // Like with grep, the color of the whole line is started before each match, and
+                // only ended after the rest of the line. Printed matches have no line color.
+                let line_color = if self.args.only_matching { "" } else { colors.line_color(context, self.args.invert) };
+                let mut pos = 0;
+
+                // Without a color for the matches, they are not highlighted at all.
+                let matches = if colors.highlights(context) { matches } else { &[] };
+                for (found, pattern) in matches {
+                    if !line_color.is_empty() {
+                        write!(self.out, "{}", colors.start(line_color))?;
+                    }
+                    self.out.write_all(&content[pos..found.start])?;
+                    paint(&mut self.out, colors, colors.match_color(*pattern, context), &content[found.clone()])?;
+                    pos = found.end;
+                }
+                paint(&mut self.out, colors, line_color, &content[pos..])?;
+            },
+            None => self.out.write_all(content)?
+        }
+
+        if truncated {
+            self.out.write_all(b" [... omitted end of long line]")?;
+        }
+        return self.out.write_all(&[self.args.terminator()]);
+    }
+
+    /// Prints a line as a JSON record: the line itself, with its position and the matches in it.
+    fn json_line(&mut self, path: &str, number: Option<usize>, offset: usize, content: &[u8], matches: &[(Range<usize>, usize)], context: bool) -> Result<()> {
+        let out = &mut self.out;
+
+        write!(out, "{{\"type\":\"{}\",\"data\":{{\"path\":", if context { "context" } else { "match" })?;
+        json::write_data(out, path.as_bytes())?;
+        out.write_all(b",\"lines\":")?;
+        json::write_data(out, content)?;
+        match number {
+            Some(number) => write!(out, ",\"line_number\":{}", number)?,
+            None => out.write_all(b",\"line_number\":null")?
+        }
+        write!(out, ",\"absolute_offset\":{},\"submatches\":[", offset)?;
+
+        // The offsets of the matches are relative to the start of the line.
+        for (i, (found, pattern)) in matches.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+            out.write_all(b"{\"match\":")?;
+            json::write_data(out, &content[found.clone()])?;
+            write!(out, ",\"pattern\":{},\"start\":{},\"end\":{}}}", pattern, found.start, found.end)?;
+        }
+        out.write_all(b"]}}\n")?;
+
+        if !context {
+            if self.last_line != Some(offset) {
+                self.counts.lines += 1;
+            }
+            self.counts.matches += matches.len();
+            self.last_line = Some(offset);
+        }
+
+        return Ok(());
+    }
+
+    /// Prints the record of the start of a file, if the output is JSON records.
+    pub fn begin(&mut self, path: &str) -> Result<()> {
+        self.counts = Counts::default();
+        self.last_line = None;
+
+        if !self.json() {
+            return Ok(());
+        }
+
+        self.out.write_all(b"{\"type\":\"begin\",\"data\":{\"path\":")?;
+        json::write_data(&mut self.out, path.as_bytes())?;
+        return self.out.write_all(b"}}\n");
+    }
+
+    /// Prints the record of the end of a file, with the numbers of its results,
+    /// if the output is JSON records.
+    pub fn end(&mut self, path: &str, binary: bool, selected: bool) -> Result<()> {
+        self.totals.files += 1;
+        self.totals.files_matched += selected as usize;
+        self.totals.lines += self.counts.lines;
+        self.totals.matches += self.counts.matches;
+
+        if !self.json() {
+            return Ok(());
+        }
+
+        self.out.write_all(b"{\"type\":\"end\",\"data\":{\"path\":")?;
+        json::write_data(&mut self.out, path.as_bytes())?;
+        return writeln!(
+            self.out,
+            ",\"binary\":{},\"selected\":{},\"stats\":{{\"matched_lines\":{},\"matches\":{}}}}}}}",
+            binary, selected, self.counts.lines, self.counts.matches
+        );
+    }
+
+    /// Prints the record of the numbers of the results in every file, if the output is JSON records.
+    pub fn summary(&mut self) -> Result<()> {
+        if !self.json() {
+            return Ok(());
+        }
+
+        return writeln!(
+            self.out,
+            "{{\"type\":\"summary\",\"data\":{{\"files\":{},\"files_matched\":{},\"matched_lines\":{},\"matches\":{}}}}}",
+            self.totals.files, self.totals.files_matched, self.totals.lines, self.totals.matches
+        );
+    }
+
+    /// Counts the bytes of a searched part of a file.
+    pub fn add_scanned(&mut self, bytes: usize) {
+        self.scanned += bytes;
+    }
+
+    /// Returns the number of bytes searched in every file.
+    pub fn scanned(&self) -> usize {
+        return self.scanned;
+    }
+
+    /// Prints the separator between groups of lines that are not next to each other.
+    pub fn group_separator(&mut self) -> Result<()> {
+        if self.json() {
+            return Ok(());
+        }
+
+        self.write(|c| &c.separator, b"--")?;
+        return self.out.write_all(b"\n");
+    }
+
+    /// Prints the name of a file.
+    pub fn path(&mut self, path: &str) -> Result<()> {
+        self.write(|c| &c.filename, path.as_bytes())?;
+        return self.out.write_all(b"\n");
+    }
+
+    /// Prints the notice that a binary file has selected lines, instead of the lines themselves.
+    /// The notice is left out of JSON records, where the end of the file tells it instead.
+    pub fn binary_match(&mut self, path: &str) -> Result<()> {
+        if self.json() {
+            return Ok(());
+        }
+
+        return writeln!(self.out, "Binary file {} matches", path);
+    }
+
+    /// Prints the number of selected lines in a file.
+    pub fn count(&mut self, path: &str, count: usize) -> Result<()> {
+        if self.args.filenames() {
+            self.write(|c| &c.filename, path.as_bytes())?;
+            self.write(|c| &c.separator, b":")?;
+        }
+
+        return writeln!(self.out, "{}", count);
+    }
+
+    /// Prints the start and end byte offsets of a match.
+    pub fn tuple(&mut self, start: usize, end: usize) -> Result<()> {
+        return writeln!(self.out, "({}, {})", start, end);
+    }
+
+    /// Writes the buffered output.
+    pub fn flush(&mut self) -> Result<()> {
+        return self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns what painting the content in the given color writes.
+    fn painted(colors: &Colors, color: &str, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        paint(&mut out, colors, color, content).unwrap();
+        return out;
+    }
+
+    /// Test that the content is only wrapped in the color sequences if both are non-empty.
+    #[test]
+    fn test_paint() {
+        let colors = Colors::default();
+
+        assert_eq!(b"\x1b[01;31m\x1b[Kfoo\x1b[m\x1b[K".to_vec(), painted(&colors, "01;31", b"foo"));
+        assert_eq!(b"foo".to_vec(), painted(&colors, "", b"foo"));
+        assert_eq!(b"".to_vec(), painted(&colors, "01;31", b""));
+    }
+
+    /// Test that the line is not cleared after the colored parts without erasing.
+    #[test]
+    fn test_paint_no_erase() {
+        let colors = Colors::parse("ne");
+        assert_eq!(b"\x1b[35mfoo\x1b[m".to_vec(), painted(&colors, "35", b"foo"));
+    }
+}
@@ -0,0 +1,192 @@
+use std::{fs::{self, OpenOptions}, io::{self, ErrorKind, Write}, ops::Range, path::{Path, PathBuf}, process};
+
+use regex::bytes::{Regex as BytesRegex, RegexBuilder as BytesRegexBuilder};
+
+use crate::{args::Args, search::{self, Engine}, text::{self, BinaryFiles, Input, Text}};
+
+/// Substitutes the matches of the patterns with their replacement templates.
+pub struct Replacer {
+    /// Each pattern, anchored to both ends of a match, to find its groups. The anchors
+    /// of the patterns themselves match at every line, like in the search.
+    patterns: Vec<BytesRegex>,
+    /// The pattern used for the matches that none of the patterns match on their own,
+    /// like the ones containing invalid UTF-8. It only has the whole match as a group.
+    fallback: BytesRegex,
+    /// The replacement template of each pattern, or a single one for every pattern.
+    templates: Vec<String>,
+}
+
+impl Replacer {
+    /// Creates a replacer of the given patterns, with the replacement templates of the arguments.
+    pub fn new(args: &Args, patterns: &[&str]) -> Result<Replacer, &'static str> {
+        if args.replacements.len() != 1 && args.replacements.len() != patterns.len() {
+            return Err("The number of replacements must be one, or the number of patterns!");
+        }
+
+        let mut anchored = vec![];
+        for pattern in patterns {
+            let pattern = if args.fixed_strings { regex::escape(pattern) } else { pattern.to_string() };
+            let compiled = BytesRegexBuilder::new(&format!(r"\A(?:{})\z", pattern))
+                .line_terminator(args.terminator())
+                .crlf(args.crlf)
+                .multi_line(true)
+                .build();
+            match compiled {
+                Ok(regex) => anchored.push(regex),
+                Err(_) => return Err("Error parsing patterns!")
+            }
+        }
+
+        let fallback = BytesRegex::new(r"(?s-u)^.*$").unwrap();
+        return Ok(Replacer { patterns: anchored, fallback, templates: args.replacements.clone() });
+    }
+
+    /// Returns the replacement of a match of the given pattern. The library-supplied matcher
+    /// doesn't tell which pattern matched, so if the given one doesn't match the whole text
+    /// of the match, the first one that does is used instead.
+    pub fn replacement(&self, found: &[u8], pattern: usize) -> Vec<u8> {
+        let captures = self.patterns.get(pattern)
+            .and_then(|regex| regex.captures(found).map(|captures| (captures, pattern)))
+            .or_else(|| self.patterns.iter().enumerate().find_map(|(i, regex)| regex.captures(found).map(|captures| (captures, i))));
+
+        let (captures, pattern) = match captures {
+            Some(captures) => captures,
+            None => (self.fallback.captures(found).unwrap(), pattern)
+        };
+
+        let template = &self.templates[if self.templates.len() == 1 { 0 } else { pattern }];
+        let mut replacement = vec![];
+        captures.expand(template.as_bytes(), &mut replacement);
+        return replacement;
+    }
+
+    /// Replaces the given matches of the patterns in the content. Returns the replaced
+    /// content, with the ranges of the replacements in it.
+    pub fn replace(&self, content: &[u8], matches: &[(Range<usize>, usize)]) -> (Vec<u8>, Vec<(Range<usize>, usize)>) {
+        let mut replaced = Vec::with_capacity(content.len());
+        let mut ranges = vec![];
+        let mut pos = 0;
+
+        for (found, pattern) in matches {
+            replaced.extend_from_slice(&content[pos..found.start]);
+
+            let start = replaced.len();
+            replaced.extend(self.replacement(&content[found.clone()], *pattern));
+            ranges.push((start..replaced.len(), *pattern));
+
+            pos = found.end;
+        }
+        replaced.extend_from_slice(&content[pos..]);
+
+        return (replaced, ranges);
+    }
+}
+
+/// Returns the path of the temporary file a file is rewritten into, in the same directory,
+/// so that it can be renamed over the file.
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map_or(String::new(), |name| name.to_string_lossy().to_string());
+    return path.with_file_name(format!(".{}.fregex-{}.tmp", name, process::id()));
+}
+
+/// Writes the contents into a new file at the given path, with the given permissions.
+fn write_new(path: &Path, contents: &[u8], permissions: fs::Permissions) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(contents)?;
+    file.set_permissions(permissions)?;
+    return file.sync_all();
+}
+
+/// Replaces every match in a file, and writes the result back into it. The result is
+/// written into a temporary file first, which is then renamed over the file, so the
+/// file is never left half-written. Symbolic links are resolved first, so that the file
+/// they point to is replaced, not the link itself. Binary files are left alone, unless
+/// they are searched like text. Returns whether anything was replaced.
+pub fn in_place(engine: &Engine, replacer: &Replacer, path: &Path, args: &Args) -> io::Result<bool> {
+    let path = &fs::canonicalize(path)?;
+    let contents = match text::open(path, false)? {
+        Input::Whole(contents) => contents,
+        Input::Stream(_) => return Err(io::Error::new(ErrorKind::InvalidInput, "Only regular files can be replaced in place"))
+    };
+    if text::is_binary(&contents, args.terminator()) && args.binary_files != BinaryFiles::Text {
+        return Ok(false);
+    }
+
+    let text = Text::new(&contents, args.terminator());
+    let matches = search::matches(engine, &text);
+    if matches.is_empty() {
+        return Ok(false);
+    }
+
+    let (replaced, _) = replacer.replace(&contents, &matches);
+    let temporary = temporary_path(path);
+
+    // Don't leave the temporary file behind, if it couldn't be written or moved.
+    let result = write_new(&temporary, &replaced, fs::metadata(path)?.permissions())
+        .and_then(|_| fs::rename(&temporary, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+
+    return result.map(|_| true);
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    /// Creates the replacer of the patterns and templates of the given command line arguments.
+    fn compile(arguments: &[&str]) -> Result<Replacer, &'static str> {
+        let args = Args::parse_from(["fregex_grep"].iter().chain(arguments));
+        let patterns: Vec<&str> = args.patterns.iter().map(|p| p.as_str()).collect();
+        return Replacer::new(&args, &patterns);
+    }
+
+    /// Test that the groups of the matching pattern are expanded in its template.
+    #[test]
+    fn test_replacement() {
+        let replacer = compile(&["-p", "(a+)(b)", "-p", "c(?<d>d)", "--replace", "$2$1", "--replace", "[$d]"]).unwrap();
+
+        assert_eq!(b"baa".to_vec(), replacer.replacement(b"aab", 0));
+        assert_eq!(b"[d]".to_vec(), replacer.replacement(b"cd", 1));
+        // The first pattern matching the whole text is used if the given one doesn't.
+        assert_eq!(b"[d]".to_vec(), replacer.replacement(b"cd", 0));
+    }
+
+    /// Test that the matches are replaced, and the ranges of the replacements are returned.
+    #[test]
+    fn test_replace() {
+        let replacer = compile(&["-p", "o+", "--replace", "<$0>"]).unwrap();
+        let (replaced, ranges) = replacer.replace(b"foo boo", &[(1..3, 0), (5..7, 0)]);
+
+        assert_eq!(b"f<oo> b<oo>".to_vec(), replaced);
+        assert_eq!(vec![(1..5, 0), (7..11, 0)], ranges);
+    }
+
+    /// Test that the anchors of the patterns match at the lines of a match, not only at its ends.
+    #[test]
+    fn test_anchored_patterns() {
+        let replacer = compile(&["-p", "a$\\n^b", "--replace", "X"]).unwrap();
+        assert_eq!(b"X".to_vec(), replacer.replacement(b"a\nb", 0));
+
+        let replacer = compile(&["-F", "-p", "a.b", "--replace", "[$0]"]).unwrap();
+        assert_eq!(b"[a.b]".to_vec(), replacer.replacement(b"a.b", 0));
+    }
+
+    /// Test that the matches no pattern matches on its own, like invalid UTF-8, are still replaced.
+    #[test]
+    fn test_fallback() {
+        let replacer = compile(&["-p", "x", "--replace", "[$0]"]).unwrap();
+        assert_eq!(b"[\xff]".to_vec(), replacer.replacement(b"\xff", 0));
+    }
+
+    /// Test that there must be a single template, or one for each pattern.
+    #[test]
+    fn test_template_count() {
+        assert!(compile(&["-p", "a", "-p", "b", "-p", "c", "--replace", "x", "--replace", "y"]).is_err());
+        assert!(compile(&["-p", "a", "-p", "b", "--replace", "x"]).is_ok());
+        assert!(compile(&["-p", "(", "--replace", "x"]).is_err());
+    }
+}
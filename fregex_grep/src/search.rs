@@ -0,0 +1,839 @@
+use std::{cmp::{max, min}, io::{self, ErrorKind, Read, Result}, ops::Range};
+
+use fregex::RegexMatcher;
+use regex::Regex as OriginalRegex;
+use regex_syntax::{hir::{Class, Hir, HirKind}, ParserBuilder};
+
+use crate::{args::Args, output::Printer, text::{self, BinaryFiles, Text}};
+
+/// The matcher used for searching: either the custom one, or the library-supplied one.
+pub enum Engine<'p> {
+    Fregex(Box<dyn RegexMatcher + 'p>),
+    Original(OriginalRegex),
+}
+
+impl Engine<'_> {
+    /// Finds the first match in the text after the given offset, and returns its byte range.
+    /// The text before the offset is still seen by the anchors and word boundaries.
+    pub fn find_from(&self, text: &str, start: usize) -> Option<Range<usize>> {
+        return match self {
+            Engine::Fregex(matcher) => matcher.find_at(text, start).map(|m| m.start()..m.end()),
+            Engine::Original(matcher) => matcher.find_at(text, start).map(|m| m.range()),
+        };
+    }
+
+    /// Finds the first match in the text after the given offset, and returns its byte range
+    /// with the id of the matching pattern. The library-supplied matcher always returns 0.
+    pub fn find_with_id_from(&self, text: &str, start: usize) -> Option<(Range<usize>, usize)> {
+        return match self {
+            Engine::Fregex(matcher) => matcher.find_with_id_at(text, start).map(|(m, id)| (m.start()..m.end(), id)),
+            Engine::Original(matcher) => matcher.find_at(text, start).map(|m| (m.range(), 0)),
+        };
+    }
+}
+
+/// Determines whether a match of the pattern may contain the line terminator, that is,
+/// whether it may span multiple lines. Patterns that can't be parsed are assumed to.
+pub fn crosses_lines(pattern: &str, args: &Args) -> bool {
+    let mut parser = ParserBuilder::new().multi_line(true).crlf(args.crlf).line_terminator(args.terminator()).build();
+
+    return match parser.parse(pattern) {
+        Ok(hir) => matches_byte(&hir, args.terminator()),
+        Err(_) => true,
+    };
+}
+
+/// Determines whether any of the characters matched by the expression may be the given byte.
+fn matches_byte(hir: &Hir, byte: u8) -> bool {
+    return match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => false,
+        HirKind::Literal(literal) => literal.0.contains(&byte),
+        HirKind::Class(Class::Unicode(class)) => class.ranges().iter().any(|r| r.start() <= byte as char && byte as char <= r.end()),
+        HirKind::Class(Class::Bytes(class)) => class.ranges().iter().any(|r| r.start() <= byte && byte <= r.end()),
+        HirKind::Repetition(repetition) => matches_byte(&repetition.sub, byte),
+        HirKind::Capture(capture) => matches_byte(&capture.sub, byte),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => hirs.iter().any(|hir| matches_byte(hir, byte)),
+    };
+}
+
+/// The number of bytes read from a stream at once.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// An error that stopped the search of a file.
+pub enum SearchError {
+    /// The text of the file couldn't be read.
+    Read(io::Error),
+    /// The results couldn't be written.
+    Write(io::Error),
+}
+
+/// Counts the lines of a text up to increasing positions, so that
+/// every byte of the text is only scanned once.
+pub struct LineCounter {
+    /// The position the lines were counted up to.
+    pos: usize,
+    /// The number of the line the position is in.
+    line: usize,
+    /// The byte ending each line.
+    terminator: u8,
+}
+
+impl LineCounter {
+    /// Creates a new counter at the start of a text, whose lines end with the given byte.
+    pub fn new(terminator: u8) -> LineCounter {
+        return LineCounter { pos: 0, line: 1, terminator };
+    }
+
+    /// Returns the number of the line the given position is in. The position
+    /// must not be before the one given in the previous call.
+    pub fn line_of(&mut self, text: &str, pos: usize) -> usize {
+        self.line += text.as_bytes()[self.pos..pos].iter().filter(|b| **b == self.terminator).count();
+        self.pos = pos;
+        return self.line;
+    }
+
+    /// Moves the counter to the next chunk of the text, which starts at the given
+    /// position of the current chunk.
+    fn advance(&mut self, text: &str, pos: usize) {
+        self.line_of(text, pos);
+        self.pos = 0;
+    }
+}
+
+/// Returns the start of the line the given position is in. The terminators are ASCII,
+/// so they are searched for as characters.
+fn line_start(text: &str, pos: usize, terminator: u8) -> usize {
+    return text[..pos].rfind(terminator as char).map_or(0, |i| i + 1);
+}
+
+/// Returns the end of the line starting at the given position, without its terminator.
+fn line_end_from(text: &str, pos: usize, terminator: u8) -> usize {
+    return text[pos..].find(terminator as char).map_or(text.len(), |i| pos + i);
+}
+
+/// Returns the end of the last line of the given match, without its line terminator.
+fn line_end(text: &str, found: &Range<usize>, terminator: u8) -> usize {
+    // A match ending with a line terminator doesn't reach into the next line.
+    let from = if found.end > found.start && text.as_bytes()[found.end - 1] == terminator {
+        found.end - 1
+    } else {
+        found.end
+    };
+
+    return line_end_from(text, from, terminator);
+}
+
+/// Returns the end of the given number of lines from the given line start, or the given end,
+/// if there are fewer lines before it. Also returns the number of lines up to the returned end.
+fn take_lines(text: &str, start: usize, end: usize, count: usize, terminator: u8) -> (usize, usize) {
+    let mut lines = 1;
+
+    for (i, b) in text.as_bytes()[start..end].iter().enumerate() {
+        if *b == terminator {
+            if lines == count {
+                return (start + i, lines);
+            }
+            lines += 1;
+        }
+    }
+
+    return (end, lines);
+}
+
+/// Determines whether a match starts within a line of the text. In multi-line mode, an empty
+/// match like `^` may be found at the very end of a text ending with a line terminator, which
+/// is not part of any line (and in a chunk of a stream, is part of the next chunk).
+fn within_lines(text: &str, found: &Range<usize>, terminator: u8) -> bool {
+    return found.start < text.len() || text.as_bytes().last().is_none_or(|b| *b != terminator);
+}
+
+/// Returns the position after the given match, where the search continues.
+/// Empty matches are stepped over, so that the search doesn't get stuck.
+fn after(text: &str, found: &Range<usize>) -> usize {
+    if found.is_empty() {
+        return found.end + text[found.end..].chars().next().map_or(1, |c| c.len_utf8());
+    }
+
+    return found.end;
+}
+
+/// Finds the non-empty matches within a text, like the ones to be highlighted in a line.
+pub fn matches(engine: &Engine, text: &str) -> Vec<(Range<usize>, usize)> {
+    let mut matches = vec![];
+    let mut pos = 0;
+
+    while pos < text.len() {
+        let (found, pattern) = match engine.find_with_id_from(text, pos) {
+            Some(found) => found,
+            None => break
+        };
+        pos = after(text, &found);
+
+        if !found.is_empty() {
+            matches.push((found, pattern));
+        }
+    }
+
+    return matches;
+}
+
+/// The state of printing the results of a search in a single file. The positions
+/// within the current chunk of the text are relative to its start, while the ones
+/// that have to be kept between the chunks are relative to the start of the file.
+struct Output<'s, 'a, 'p> {
+    /// The matcher used for finding the matches to highlight.
+    engine: &'s Engine<'s>,
+    /// The path of the searched file.
+    path: &'s str,
+    /// The arguments deciding the format of the output.
+    args: &'a Args,
+    /// The printer of the results.
+    printer: &'p mut Printer<'a>,
+    /// The counter of the line numbers, only used if they are printed.
+    lines: LineCounter,
+    /// The position of the current chunk in the file.
+    base: usize,
+    /// The start of the first line that wasn't printed yet, in the file.
+    printed_to: usize,
+    /// Whether any line was printed yet.
+    printed_any: bool,
+    /// The number of lines to print after each matching line.
+    after: usize,
+    /// The number of lines to print before each matching line.
+    before: usize,
+    /// The number of context lines still to be printed after the last matching line.
+    after_remaining: usize,
+    /// Whether the groups of lines are separated, which they are if any context was requested.
+    separated: bool,
+    /// The byte ending each line.
+    terminator: u8,
+}
+
+impl<'s, 'a, 'p> Output<'s, 'a, 'p> {
+    /// Creates the output state of the given file.
+    fn new(engine: &'s Engine<'s>, path: &'s str, args: &'a Args, printer: &'p mut Printer<'a>) -> Output<'s, 'a, 'p> {
+        return Output {
+            engine,
+            path,
+            args,
+            printer,
+            lines: LineCounter::new(args.terminator()),
+            base: 0,
+            printed_to: 0,
+            printed_any: false,
+            after: args.after_context.or(args.context).unwrap_or(0),
+            before: args.before_context.or(args.context).unwrap_or(0),
+            after_remaining: 0,
+            separated: args.after_context.is_some() || args.before_context.is_some() || args.context.is_some(),
+            terminator: args.terminator(),
+        };
+    }
+
+    /// Returns the position of the first line of the chunk that wasn't printed yet.
+    fn unprinted(&self, text: &str) -> usize {
+        return min(self.printed_to.saturating_sub(self.base), text.len());
+    }
+
+    /// Prints the line starting at the given position, and returns the start of the next line.
+    fn print_line(&mut self, text: &Text, start: usize, context: bool) -> Result<usize> {
+        let end = line_end_from(text, start, self.terminator);
+        let number = if self.args.line_numbers() { Some(self.lines.line_of(text, start)) } else { None };
+        let matches = if self.printer.highlights() { matches(self.engine, &text[start..end]) } else { vec![] };
+
+        self.printer.line(self.path, number, self.base + start, text.original(start..end), &matches, context)?;
+        self.printed_any = true;
+        self.printed_to = self.base + end + 1;
+
+        return Ok(end + 1);
+    }
+
+    /// Prints the context lines that are still due after the last matching line,
+    /// but not the ones starting at or after the given position.
+    fn print_after(&mut self, text: &Text, limit: usize) -> Result<()> {
+        while self.after_remaining > 0 && self.unprinted(text) < min(limit, text.len()) {
+            self.print_line(text, self.unprinted(text), true)?;
+            self.after_remaining -= 1;
+        }
+
+        return Ok(());
+    }
+
+    /// Prints the lines from the given start to the given end, with the context lines
+    /// before them. Lines that were already printed are not printed again, and groups
+    /// of lines that are not next to each other are separated.
+    fn print_match(&mut self, text: &Text, start: usize, end: usize) -> Result<()> {
+        self.print_after(text, start)?;
+
+        // Step back over the context lines, scanning backwards from the match.
+        let unprinted = self.unprinted(text);
+        let mut first = start;
+        for _ in 0..self.before {
+            if first <= unprinted {
+                break;
+            }
+            first = line_start(text, first - 1, self.terminator);
+        }
+        let first = max(first, unprinted);
+
+        if self.separated && self.printed_any && self.base + first > self.printed_to {
+            self.printer.group_separator()?;
+        }
+
+        let mut pos = first;
+        while pos < start {
+            pos = self.print_line(text, pos, true)?;
+        }
+        while pos <= end && pos < text.len() {
+            pos = self.print_line(text, pos, false)?;
+        }
+
+        self.after_remaining = self.after;
+        return Ok(());
+    }
+
+    /// Returns the position from which the current chunk has to be kept, as the
+    /// context before the matches of the next chunk.
+    fn retained(&self, text: &str) -> usize {
+        let mut start = text.len();
+        for _ in 0..self.before {
+            if start == 0 {
+                break;
+            }
+            start = line_start(text, start - 1, self.terminator);
+        }
+
+        return max(start, self.unprinted(text));
+    }
+
+    /// Moves to the next chunk of the text, which starts at the given position of the current chunk.
+    fn advance(&mut self, text: &str, pos: usize) {
+        if self.args.line_numbers() {
+            self.lines.advance(text, pos);
+        }
+        self.base += pos;
+    }
+}
+
+/// Calls the given function with the first line start and the last line end of each group
+/// of selected lines after the given position: the lines of each match, or with inverted
+/// matching, the lines between them. The lines end with the given terminator. The search
+/// stops once the function returns false.
+fn for_each_selected<F>(engine: &Engine, text: &str, from: usize, invert: bool, terminator: u8, mut function: F) -> Result<()>
+where F: FnMut(usize, usize) -> Result<bool> {
+    let mut pos = from;
+
+    while pos < text.len() {
+        let found = engine.find_from(text, pos).filter(|found| within_lines(text, found, terminator));
+        let (start, end) = match &found {
+            Some(found) => (line_start(text, found.start, terminator), line_end(text, found, terminator)),
+            None => (text.len(), text.len())
+        };
+
+        // The lines before the match are only selected with inverted matching. The last
+        // one of them ends just before the start of the match.
+        let proceed = if invert {
+            start <= pos || function(pos, start - 1)?
+        } else {
+            found.is_none() || function(start, end)?
+        };
+
+        if !proceed || found.is_none() {
+            break;
+        }
+        pos = end + 1;
+    }
+
+    return Ok(());
+}
+
+/// Counts the selected lines after the given position, up to the given limit. If only the
+/// first group of them is needed, the search stops there.
+fn count_lines(engine: &Engine, text: &str, from: usize, args: &Args, first_only: bool, limit: usize) -> Result<usize> {
+    let mut count = 0;
+    let terminator = args.terminator();
+
+    for_each_selected(engine, text, from, args.invert, terminator, |start, end| {
+        count += take_lines(text, start, end, limit - count, terminator).1;
+        return Ok(!first_only && count < limit);
+    })?;
+
+    return Ok(count);
+}
+
+/// Determines whether the text has any selected line after the given position,
+/// stopping the search at the first one.
+fn any_selected(engine: &Engine, text: &str, from: usize, args: &Args) -> Result<bool> {
+    if args.invert {
+        return Ok(count_lines(engine, text, from, args, true, 1)? > 0);
+    }
+
+    return Ok(engine.find_from(text, from).is_some_and(|found| within_lines(text, &found, args.terminator())));
+}
+
+/// The search of a single file, in the format chosen by the arguments. The text of the file
+/// may be searched in multiple chunks, each of which must consist of whole lines (except for
+/// the last one). The lines of the matches (and their context) are only located once the
+/// matches are found. The lines of binary files are not printed, only whether any of them
+/// was selected.
+pub struct Search<'s, 'a, 'p> {
+    /// The matcher used for searching.
+    engine: &'s Engine<'s>,
+    /// The state of the printed lines.
+    output: Output<'s, 'a, 'p>,
+    /// Whether the file is binary.
+    binary: bool,
+    /// Whether any line was selected yet.
+    selected: bool,
+    /// The number of selected lines, only counted if more than their presence matters.
+    count: usize,
+    /// Whether the rest of the text doesn't have to be searched.
+    done: bool,
+}
+
+impl<'s, 'a, 'p> Search<'s, 'a, 'p> {
+    /// Starts the search of a file. Binary files may be searched like text, or not at all.
+    pub fn new(engine: &'s Engine<'s>, path: &'s str, binary: bool, args: &'a Args, printer: &'p mut Printer<'a>) -> Search<'s, 'a, 'p> {
+        let binary = binary && args.binary_files != BinaryFiles::Text;
+        let done = (binary && args.binary_files == BinaryFiles::WithoutMatch) || args.max_count == Some(0);
+
+        return Search { engine, output: Output::new(engine, path, args, printer), binary, selected: false, count: 0, done };
+    }
+
+    /// Determines whether only the presence of a selected line matters, so the search can stop at the first one.
+    fn presence_only(&self) -> bool {
+        let args = self.output.args;
+
+        // The inverted lines have no matching parts to print.
+        return args.quiet || args.files_with_matches || args.files_without_match
+            || (!args.count && (self.binary || (args.only_matching && args.invert)));
+    }
+
+    /// Determines whether the selected lines are printed, with their context.
+    fn prints_lines(&self) -> bool {
+        let args = self.output.args;
+        return !self.presence_only() && !args.count && !args.tuples && !args.only_matching;
+    }
+
+    /// Determines whether the rest of the text doesn't have to be searched.
+    pub fn done(&self) -> bool {
+        return self.done;
+    }
+
+    /// Returns the number of lines that may still be selected before the maximum count is reached.
+    fn remaining(&self) -> usize {
+        return self.output.args.max_count.map_or(usize::MAX, |max| max.saturating_sub(self.count));
+    }
+
+    /// Skips the text before the given position: its lines are neither searched nor printed
+    /// as context, and a line starting before it is only printed from there.
+    pub fn skip_to(&mut self, pos: usize) {
+        self.output.printed_to = self.output.base + pos;
+    }
+
+    /// Searches a chunk of the text, from the given position. The lines before that
+    /// position were kept from the previous chunk, and are only printed as context.
+    pub fn chunk(&mut self, text: &Text, from: usize) -> Result<()> {
+        let args = self.output.args;
+        if self.done {
+            return Ok(());
+        }
+        self.output.printer.add_scanned(text.len() - from);
+
+        if self.presence_only() {
+            self.selected |= any_selected(self.engine, text, from, args)?;
+            self.done = self.selected;
+        } else if args.count {
+            self.count += count_lines(self.engine, text, from, args, args.first_only, self.remaining())?;
+            self.selected = self.count > 0;
+            self.done = (args.first_only && self.selected) || self.remaining() == 0;
+        } else if args.tuples || args.only_matching {
+            self.print_matches(text, from)?;
+        } else {
+            self.print_lines(text, from)?;
+        }
+
+        return Ok(());
+    }
+
+    /// Prints each match (or its offsets) after the given position on its own line.
+    fn print_matches(&mut self, text: &Text, from: usize) -> Result<()> {
+        let mut pos = from;
+        let mut last_line_end = None;
+        let terminator = self.output.terminator;
+
+        while pos < text.len() {
+            let (found, pattern) = match self.engine.find_with_id_from(text, pos) {
+                Some(found) if within_lines(text, &found.0, terminator) => found,
+                _ => break
+            };
+
+            // Every line with a match counts as a single selected line, and the maximum
+            // count is only reached once a match is found in the line after the last one.
+            if last_line_end.is_none_or(|end| found.start > end) {
+                if self.remaining() == 0 {
+                    self.done = true;
+                    break;
+                }
+                self.count += 1;
+                last_line_end = Some(line_end(text, &found, terminator));
+            }
+            self.selected = true;
+
+            let output = &mut self.output;
+
+            if output.args.tuples {
+                // Print the offsets of every match, as they are.
+                output.printer.tuple(output.base + found.start, output.base + found.end)?;
+            } else if !found.is_empty() {
+                // Print the matches themselves. Empty ones are not worth printing.
+                let number = if output.args.line_numbers() { Some(output.lines.line_of(text, found.start)) } else { None };
+
+                if output.printer.json() {
+                    // The JSON records contain the whole line of the match, from the start of the search.
+                    let (start, end) = (max(line_start(text, found.start, terminator), from), line_end(text, &found, terminator));
+                    let matches = [(found.start - start..found.end - start, pattern)];
+                    output.printer.line(output.path, number, output.base + start, text.original(start..end), &matches, false)?;
+                } else {
+                    let matches = [(0..found.len(), pattern)];
+                    output.printer.line(output.path, number, output.base + found.start, text.original(found.clone()), &matches, false)?;
+                }
+            }
+            pos = after(text, &found);
+
+            // If we only need the first match, break out of the loop here.
+            if output.args.first_only {
+                self.done = true;
+                break;
+            }
+        }
+
+        // The chunks end with whole lines, so the next one can't continue the last line.
+        self.done |= self.remaining() == 0;
+        return Ok(());
+    }
+
+    /// Prints the selected lines after the given position, with their context.
+    fn print_lines(&mut self, text: &Text, from: usize) -> Result<()> {
+        let mut remaining = self.remaining();
+        let output = &mut self.output;
+        let first_only = output.args.first_only;
+        let mut selected = self.selected;
+
+        // If only the first group of lines is needed, and it was already printed (or the
+        // maximum count of lines was), only the context after it may be left.
+        if !(first_only && selected) && remaining > 0 {
+            for_each_selected(self.engine, text, from, output.args.invert, output.terminator, |start, end| {
+                selected = true;
+                let (end, lines) = take_lines(text, start, end, remaining, output.terminator);
+                remaining -= lines;
+
+                output.print_match(text, start, end)?;
+                return Ok(!first_only && remaining > 0);
+            })?;
+        }
+
+        // Print the context after the last selected line of the chunk.
+        output.print_after(text, text.len())?;
+        self.count = self.output.args.max_count.map_or(0, |max| max - remaining);
+        self.selected = selected;
+        self.done = ((first_only && selected) || remaining == 0) && self.output.after_remaining == 0;
+        return Ok(());
+    }
+
+    /// Returns the position from which the current chunk has to be kept for the next one.
+    pub fn retained(&self, text: &str) -> usize {
+        if self.prints_lines() {
+            return self.output.retained(text);
+        }
+
+        return text.len();
+    }
+
+    /// Moves to the next chunk of the text, which starts at the given position of the current chunk.
+    pub fn advance(&mut self, text: &str, pos: usize) {
+        self.output.advance(text, pos);
+    }
+
+    /// Finishes the search, and prints the results that depend on the whole text.
+    /// Returns whether any line of the text was selected.
+    pub fn finish(self) -> Result<bool> {
+        let args = self.output.args;
+        let printer = self.output.printer;
+
+        if args.quiet || args.files_with_matches || args.files_without_match {
+            if !args.quiet && self.selected == args.files_with_matches {
+                printer.path(self.output.path)?;
+            }
+        } else if args.count {
+            printer.count(self.output.path, self.count)?;
+        } else if self.binary && self.selected {
+            printer.binary_match(self.output.path)?;
+        }
+
+        printer.end(self.output.path, self.binary, self.selected)?;
+        return Ok(self.selected);
+    }
+}
+
+/// Returns the range of the contents to search, given by the arguments, within the contents.
+/// Its ends are moved to the closest character boundaries within it.
+fn searched_range(contents: &[u8], args: &Args) -> Range<usize> {
+    let range = args.offset.clone().unwrap_or(0..contents.len());
+    let is_continuation = |pos: usize| pos < contents.len() && contents[pos] & 0xC0 == 0x80;
+
+    let mut end = min(range.end, contents.len());
+    while end > 0 && is_continuation(end) {
+        end -= 1;
+    }
+    let mut start = min(range.start, end);
+    while start < end && is_continuation(start) {
+        start += 1;
+    }
+
+    return start..end;
+}
+
+/// Searches the whole contents of a file at once, or only the range of them given by the
+/// arguments. Returns whether any line of the text was selected.
+pub fn search<'a>(engine: &Engine, contents: &[u8], path: &str, args: &'a Args, printer: &mut Printer<'a>) -> Result<bool> {
+    printer.begin(path)?;
+    let mut search = Search::new(engine, path, text::is_binary(contents, args.terminator()), args, printer);
+
+    // Files that aren't searched at all don't have to be validated either. The text before
+    // the range is validated too, since it is seen by the matcher at the start of the range.
+    if !search.done() {
+        let range = searched_range(contents, args);
+        search.skip_to(range.start);
+        search.chunk(&Text::new(&contents[..range.end], args.terminator()), range.start)?;
+    }
+    return search.finish();
+}
+
+/// Reads more of the stream to the end of the buffer. Returns whether the end of the stream was reached.
+fn fill(reader: &mut impl Read, buffer: &mut Vec<u8>) -> io::Result<bool> {
+    let length = buffer.len();
+    buffer.resize(length + BUFFER_SIZE, 0);
+
+    loop {
+        match reader.read(&mut buffer[length..]) {
+            Ok(read) => {
+                buffer.truncate(length + read);
+                return Ok(read == 0);
+            },
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => {
+                buffer.truncate(length);
+                return Err(error);
+            }
+        }
+    }
+}
+
+/// Searches the text of a stream, reading it in parts. The whole lines read so far are
+/// searched at once, and only the lines that may be printed as context are kept, so the
+/// matches within a line are found even if the line was split between the reads. Matches
+/// spanning multiple lines may be split between the reads too, so the patterns that may
+/// cross lines have to be searched in the whole text instead.
+/// Returns whether any line of the text was selected.
+pub fn search_stream<'a>(engine: &Engine, mut reader: impl Read, path: &str, args: &'a Args, printer: &mut Printer<'a>) -> std::result::Result<bool, SearchError> {
+    let mut buffer = vec![];
+    let mut eof = false;
+
+    // Read the start of the stream first, to decide whether it is binary.
+    while !eof && buffer.len() < BUFFER_SIZE {
+        eof = fill(&mut reader, &mut buffer).map_err(SearchError::Read)?;
+    }
+    printer.begin(path).map_err(SearchError::Write)?;
+    let mut search = Search::new(engine, path, text::is_binary(&buffer, args.terminator()), args, printer);
+
+    // The start of the part of the buffer that wasn't searched yet.
+    let mut searched = 0;
+    loop {
+        // Search every whole line that was read, or at the end of the stream, the rest of the text.
+        let end = if eof {
+            buffer.len()
+        } else {
+            buffer[searched..].iter().rposition(|b| *b == args.terminator()).map_or(searched, |i| searched + i + 1)
+        };
+
+        if end > searched {
+            let text = Text::new(&buffer[..end], args.terminator());
+            search.chunk(&text, searched).map_err(SearchError::Write)?;
+
+            let retained = search.retained(&text);
+            search.advance(&text, retained);
+            buffer.drain(..retained);
+            searched = end - retained;
+        }
+
+        if eof || search.done() {
+            break;
+        }
+        eof = fill(&mut reader, &mut buffer).map_err(SearchError::Read)?;
+    }
+
+    return search.finish().map_err(SearchError::Write);
+}
+
+
+
+
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use clap::Parser;
+    use regex::RegexBuilder as OriginalRegexBuilder;
+
+    use super::*;
+
+    /// Creates the library-supplied engine of a pattern, with the anchors matching at every line.
+    fn engine(pattern: &str) -> Engine<'static> {
+        return Engine::Original(OriginalRegexBuilder::new(pattern).multi_line(true).build().unwrap());
+    }
+
+    /// Test that the lines of the matches are located, without their terminators.
+    #[test]
+    fn test_line_bounds() {
+        let text = "foo\nbar baz\nqux";
+
+        assert_eq!(4, line_start(text, 8, b'\n'));
+        assert_eq!(0, line_start(text, 2, b'\n'));
+        assert_eq!(11, line_end_from(text, 4, b'\n'));
+        assert_eq!(15, line_end_from(text, 12, b'\n'));
+        assert_eq!(3, line_end(text, &(1..4), b'\n'));
+        assert_eq!(11, line_end(text, &(2..5), b'\n'));
+    }
+
+    /// Test that an empty match after the last line terminator is not part of any line.
+    #[test]
+    fn test_within_lines() {
+        assert!(!within_lines("foo\n", &(4..4), b'\n'));
+        assert!(within_lines("foo\n", &(0..0), b'\n'));
+        assert!(within_lines("foo", &(3..3), b'\n'));
+        assert!(within_lines("foo\0", &(4..4), b'\n'));
+    }
+
+    /// Test that the empty matches are stepped over, and left out of the highlighted ones.
+    #[test]
+    fn test_matches() {
+        assert_eq!(vec![(1..3, 0), (4..5, 0)], matches(&engine("x*"), "axxbxé"));
+        assert_eq!(6, after("axxbé", &(4..4)));
+        assert_eq!(3, after("axxb", &(1..3)));
+    }
+
+    /// Test that the lines are counted from position to position, across the chunks of a text.
+    #[test]
+    fn test_line_counter() {
+        let mut lines = LineCounter::new(b'\n');
+
+        assert_eq!(1, lines.line_of("a\nb\nc\nd", 1));
+        assert_eq!(3, lines.line_of("a\nb\nc\nd", 5));
+        lines.advance("a\nb\nc\nd", 6);
+        assert_eq!(5, lines.line_of("d\ne", 2));
+    }
+
+    /// Test that the given number of lines is taken from a line start, up to the end.
+    #[test]
+    fn test_take_lines() {
+        let text = "foo\nbar baz\nqux";
+
+        assert_eq!((11, 2), take_lines(text, 0, 15, 2, b'\n'));
+        assert_eq!((15, 3), take_lines(text, 0, 15, 5, b'\n'));
+        assert_eq!((3, 1), take_lines(text, 0, 15, 1, b'\n'));
+    }
+
+    /// Parses the given command line arguments, after the name of the binary.
+    fn args(arguments: &[&str]) -> Args {
+        return Args::parse_from(["fregex_grep"].iter().chain(arguments));
+    }
+
+    /// Returns the groups of lines selected by the pattern in the text.
+    fn selected(pattern: &str, text: &str, invert: bool) -> Vec<(usize, usize)> {
+        let mut groups = vec![];
+        let result = for_each_selected(&engine(pattern), text, 0, invert, b'\n', |start, end| {
+            groups.push((start, end));
+            return Ok(true);
+        });
+
+        assert!(result.is_ok());
+        return groups;
+    }
+
+    /// Test that the lines of the matches are selected, or with inverted matching, the lines between them.
+    #[test]
+    fn test_selected_lines() {
+        let text = "foo\nbar\nfoo bar\nbaz\n";
+
+        assert_eq!(vec![(0, 3), (8, 15)], selected("foo", text, false));
+        assert_eq!(vec![(4, 7), (16, 19)], selected("foo", text, true));
+        assert_eq!(vec![(4, 15)], selected("bar\nfoo", text, false));
+        assert_eq!(vec![(4, 7)], selected("^bar$", text, false));
+        assert_eq!(Vec::<(usize, usize)>::new(), selected("^", "", false));
+    }
+
+    /// Test that the counted lines stop at the limit, and at the first group if only it is needed.
+    #[test]
+    fn test_count_lines() {
+        let text = "foo\nfoo\nbar\nfoo\n";
+        let arguments = args(&["-p", "foo"]);
+
+        assert_eq!(3, count_lines(&engine("foo"), text, 0, &arguments, false, usize::MAX).unwrap());
+        assert_eq!(2, count_lines(&engine("foo"), text, 0, &arguments, false, 2).unwrap());
+        assert_eq!(2, count_lines(&engine("foo\nfoo"), text, 0, &arguments, true, usize::MAX).unwrap());
+        assert_eq!(1, count_lines(&engine("foo"), text, 4, &arguments, true, usize::MAX).unwrap());
+
+        let arguments = args(&["-v", "-p", "foo"]);
+        assert_eq!(1, count_lines(&engine("foo"), text, 0, &arguments, false, usize::MAX).unwrap());
+        assert!(any_selected(&engine("foo"), text, 0, &arguments).unwrap());
+        assert!(!any_selected(&engine("foo"), "foo\nfoo\n", 0, &arguments).unwrap());
+    }
+
+    /// Test that the stream is read into the end of the buffer, until its end.
+    #[test]
+    fn test_fill() {
+        let mut reader = Cursor::new(vec![b'x'; BUFFER_SIZE + 10]);
+        let mut buffer = b"ab".to_vec();
+
+        assert!(!fill(&mut reader, &mut buffer).unwrap());
+        assert_eq!(BUFFER_SIZE + 2, buffer.len());
+        assert!(!fill(&mut reader, &mut buffer).unwrap());
+        assert_eq!(BUFFER_SIZE + 12, buffer.len());
+        assert!(fill(&mut reader, &mut buffer).unwrap());
+        assert_eq!(BUFFER_SIZE + 12, buffer.len());
+    }
+
+    /// Test that the patterns that may match the line terminator are found.
+    #[test]
+    fn test_crosses_lines() {
+        let lines = args(&["-p", "a"]);
+        let records = args(&["--null-data", "-p", "a"]);
+
+        assert!(!crosses_lines("a.b|^c$", &lines));
+        assert!(!crosses_lines(r"\w+[^\n]", &lines));
+        assert!(crosses_lines("a\nb", &lines));
+        assert!(crosses_lines(r"a\sb", &lines));
+        assert!(crosses_lines("x(?:[^a]y)*", &lines));
+        assert!(crosses_lines("(?s:.)", &lines));
+        assert!(!crosses_lines("a\nb", &records));
+        assert!(crosses_lines(r"a\x00", &records));
+        assert!(crosses_lines("(", &lines));
+    }
+
+    /// Test that the searched range is moved to the character boundaries within it.
+    #[test]
+    fn test_searched_range() {
+        let contents = "aé€b".as_bytes();
+
+        assert_eq!(0..7, searched_range(contents, &args(&["-p", "a"])));
+        assert_eq!(1..3, searched_range(contents, &args(&["--offset", "1:4", "-p", "a"])));
+        assert_eq!(3..3, searched_range(contents, &args(&["--offset", "2:5", "-p", "a"])));
+        assert_eq!(6..7, searched_range(contents, &args(&["--offset", "6:", "-p", "a"])));
+        assert_eq!(7..7, searched_range(contents, &args(&["--offset", "9:12", "-p", "a"])));
+    }
+}
@@ -1,28 +1,190 @@
-use std::{fs::File, io::Read, process::exit};
+use std::{borrow::Cow, fs::{self, File}, io::{self, stdin, Cursor, Read, Seek}, ops::{Deref, Range}, path::Path, str::from_utf8};
 
+use clap::ValueEnum;
 use memmap::{MmapOptions, Mmap};
 
-pub fn read_text(path: &str) -> String {
-    if let Ok(mut file) = File::open(path) {
-        let mut string = String::new();
+use crate::decompress::{self, Compression};
 
-        match file.read_to_string(&mut string) {
-            Ok(_) => return string,
-            Err(_) => { println!("Error reading input file!"); exit(1); }
-        }
+/// The size from which regular files are memory-mapped instead of being read into memory.
+const MMAP_THRESHOLD: u64 = 64 * 1024;
+
+/// The number of bytes at the start of a file that are checked for NUL bytes.
+const BINARY_CHECK_SIZE: usize = 8 * 1024;
+
+/// The character searched instead of each byte of the invalid UTF-8 sequences. It takes
+/// a single byte, so the positions in the searched text are the same as in the file.
+const SUBSTITUTE: char = '\0';
+
+/// The substitute character used when NUL bytes end the lines, so that the invalid
+/// sequences don't split the lines.
+const NUL_SUBSTITUTE: char = '\x7f';
+
+/// How binary files are searched.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum BinaryFiles {
+    /// Only print whether they have any selected line.
+    Binary,
+    /// Search them like any other file.
+    Text,
+    /// Assume that they have no selected lines, without searching them.
+    WithoutMatch,
+}
+
+/// The contents of a file: either memory-mapped, or read into memory.
+pub enum Contents {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        return match self {
+            Contents::Mapped(map) => &map[..],
+            Contents::Read(bytes) => &bytes[..],
+        };
     }
+}
 
-    println!("Error opening input file!"); exit(1);
+/// The text of a file: either available at once, or to be read in parts.
+pub enum Input {
+    Whole(Contents),
+    Stream(Box<dyn Read>),
 }
 
-pub fn read_text_mmap(path: &str) -> Mmap {
-    if let Ok(file) = File::open(path) {
+/// Opens a file (or the standard input, if the path is '-'). Large regular files are
+/// memory-mapped, and small ones are read into memory at once, while every other kind
+/// of file (like pipes or terminals) is read as a stream. If decompression is enabled,
+/// compressed files are decompressed as they are read, and searched as streams too.
+pub fn open(path: &Path, decompress: bool) -> io::Result<Input> {
+    let stream = |reader: Box<dyn Read>| {
+        return if decompress { decompress::decompressed(reader).map(Input::Stream) } else { Ok(Input::Stream(reader)) };
+    };
+
+    if path.as_os_str() == "-" {
+        return stream(Box::new(stdin().lock()));
+    }
+
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+
+    if !metadata.is_file() {
+        return stream(Box::new(file));
+    }
+
+    // Regular files that turn out not to be compressed are read from their start again.
+    if decompress {
+        let magic = decompress::read_magic(&mut file)?;
+        if let Some(compression) = Compression::detect(&magic) {
+            return compression.decoder(Cursor::new(magic).chain(file)).map(Input::Stream);
+        }
+        file.rewind()?;
+    }
+
+    if metadata.len() >= MMAP_THRESHOLD {
+        return unsafe { MmapOptions::new().map(&file) }.map(|map| Input::Whole(Contents::Mapped(map)));
+    }
+
+    let mut bytes = Vec::with_capacity(metadata.len() as usize);
+    file.read_to_end(&mut bytes)?;
+    return Ok(Input::Whole(Contents::Read(bytes)));
+}
 
-        match unsafe { MmapOptions::new().map(&file) } {
-            Ok(map) => return map,
-            Err(_) => { println!("Error reading input file!"); exit(1); }
+/// Reads the whole contents of a file, even if it would be read as a stream.
+pub fn read(path: &Path, decompress: bool) -> io::Result<Contents> {
+    return match open(path, decompress)? {
+        Input::Whole(contents) => Ok(contents),
+        Input::Stream(mut reader) => {
+            let mut bytes = vec![];
+            reader.read_to_end(&mut bytes)?;
+            Ok(Contents::Read(bytes))
         }
+    };
+}
+
+/// Determines whether the contents look like a binary file, that is, whether there's
+/// a NUL byte near their start. If NUL bytes end the lines, no file is binary.
+pub fn is_binary(contents: &[u8], terminator: u8) -> bool {
+    return terminator != 0 && contents[..contents.len().min(BINARY_CHECK_SIZE)].contains(&0);
+}
+
+/// A part of a file, validated as UTF-8 for searching. If it isn't valid UTF-8, the
+/// searched text is a copy of it, where each byte of the invalid sequences is replaced
+/// by a NUL character (or a DEL character, if NUL bytes end the lines), while the
+/// printed parts still come from the original bytes.
+pub struct Text<'b> {
+    /// The text that is searched.
+    searched: Cow<'b, str>,
+    /// The original bytes of the text.
+    bytes: &'b [u8],
+}
+
+impl<'b> Text<'b> {
+    /// Validates the bytes, copying them only if they aren't valid UTF-8. The substitutes
+    /// of the invalid bytes are never the given line terminator.
+    pub fn new(bytes: &'b [u8], terminator: u8) -> Text<'b> {
+        let searched = match from_utf8(bytes) {
+            Ok(text) => Cow::Borrowed(text),
+            Err(_) => {
+                let substitute = if terminator == SUBSTITUTE as u8 { NUL_SUBSTITUTE } else { SUBSTITUTE };
+                let mut text = String::with_capacity(bytes.len());
+                for chunk in bytes.utf8_chunks() {
+                    text.push_str(chunk.valid());
+                    text.extend(chunk.invalid().iter().map(|_| substitute));
+                }
+                Cow::Owned(text)
+            }
+        };
+
+        return Text { searched, bytes };
+    }
+
+    /// Returns the original bytes in the given range, to be printed.
+    pub fn original(&self, range: Range<usize>) -> &'b [u8] {
+        return &self.bytes[range];
+    }
+}
+
+impl Deref for Text<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        return &self.searched;
     }
+}
+
+/// Reads the patterns from a file (or from the standard input, if the path is '-'),
+/// one pattern per line. Lines starting with the comment prefix, if any, are skipped.
+pub fn read_patterns(path: &str, comment_prefix: Option<&str>) -> io::Result<Vec<String>> {
+    let content = if path == "-" {
+        let mut content = String::new();
+        stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        fs::read_to_string(path)?
+    };
+
+    let patterns = content
+        .lines()
+        .filter(|line| !comment_prefix.is_some_and(|prefix| line.starts_with(prefix)))
+        .map(|line| line.to_string())
+        .collect();
 
-    println!("Error opening input file!"); exit(1);
+    return Ok(patterns);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Text;
+
+    /// Test that the invalid bytes are substituted with a character other than the line terminator.
+    #[test]
+    fn test_substitute_terminator() {
+        let bytes = b"foo\xffbar\0";
+
+        assert_eq!("foo\0bar\0", &*Text::new(bytes, b'\n'));
+        assert_eq!("foo\x7fbar\0", &*Text::new(bytes, b'\0'));
+        assert_eq!(b"foo\xffbar", Text::new(bytes, b'\0').original(0..7));
+    }
 }
@@ -0,0 +1,91 @@
+use std::ops::Range;
+
+use fregex::{types::Explanation, RegexMatcher};
+use regex::Regex as OriginalRegex;
+
+use crate::search::{self, Engine, LineCounter};
+
+/// Compares the matches of the custom matcher with the ones of the library-supplied one.
+pub struct Verifier<'p> {
+    /// The custom matcher of the patterns.
+    fregex: Engine<'p>,
+    /// The library-supplied matcher of the alternation of the patterns.
+    original: Engine<'p>,
+    /// The library-supplied matcher of each pattern on its own, to tell which
+    /// pattern a match of the alternation belongs to.
+    patterns: Vec<OriginalRegex>,
+    /// The description of each pattern, as compiled by the custom matcher.
+    explanation: Explanation,
+    /// The byte ending each line, to tell the lines of the differing matches.
+    terminator: u8,
+}
+
+impl<'p> Verifier<'p> {
+    /// Creates a verifier of the custom matcher, with the library-supplied matchers of the same
+    /// patterns, in text whose lines end with the given terminator.
+    pub fn new(fregex: Box<dyn RegexMatcher + 'p>, original: OriginalRegex, patterns: Vec<OriginalRegex>, terminator: u8) -> Verifier<'p> {
+        let explanation = fregex.explain();
+        return Verifier { fregex: Engine::Fregex(fregex), original: Engine::Original(original), patterns, explanation, terminator };
+    }
+
+    /// Returns the pattern a match of the alternation belongs to: like in the alternation,
+    /// the first one that matches at the start of the match.
+    fn original_pattern(&self, text: &str, found: &Range<usize>) -> usize {
+        return self.patterns
+            .iter()
+            .position(|pattern| pattern.find_at(text, found.start).is_some_and(|m| m.start() == found.start))
+            .unwrap_or(0);
+    }
+
+    /// Describes a match, with its pattern and the heuristic chosen for it.
+    fn describe(&self, text: &str, found: Option<&(Range<usize>, usize)>) -> String {
+        let (found, id) = match found {
+            Some(found) => found,
+            None => return String::from("no match")
+        };
+
+        let pattern = &self.explanation.patterns[*id];
+        return format!(
+            "{}..{} {:?} (pattern {} `{}`, {:?})",
+            found.start, found.end, &text[found.clone()], id, pattern.pattern, pattern.suggestion
+        );
+    }
+
+    /// Finds every match of both matchers in the text, and prints the ones that differ:
+    /// the ones found by only one of them, and the ones starting at the same position, but
+    /// with a different end or pattern. Returns the number of matches of the library-supplied
+    /// matcher, and the number of differences.
+    pub fn verify(&self, text: &str, path: &str) -> (usize, usize) {
+        let actual = search::matches(&self.fregex, text);
+        let expected: Vec<(Range<usize>, usize)> = search::matches(&self.original, text)
+            .into_iter()
+            .map(|(found, _)| { let id = self.original_pattern(text, &found); return (found, id); })
+            .collect();
+
+        let mut lines = LineCounter::new(self.terminator);
+        let mut mismatches = 0;
+        let (mut i, mut j) = (0, 0);
+
+        // Both lists are ordered by the starts of the matches, so they are walked side by side.
+        while i < actual.len() || j < expected.len() {
+            let (fregex, original) = match (actual.get(i), expected.get(j)) {
+                (Some(a), Some(e)) if a.0.start == e.0.start => { i += 1; j += 1; (Some(a), Some(e)) },
+                (Some(a), Some(e)) if a.0.start < e.0.start => { i += 1; (Some(a), None) },
+                (Some(a), None) => { i += 1; (Some(a), None) },
+                (_, e) => { j += 1; (None, e) }
+            };
+            if fregex == original {
+                continue;
+            }
+
+            let start = fregex.or(original).map_or(0, |(found, _)| found.start);
+            println!(
+                "{}:{}: fregex: {}; regex: {}",
+                path, lines.line_of(text, start), self.describe(text, fregex), self.describe(text, original)
+            );
+            mismatches += 1;
+        }
+
+        return (expected.len(), mismatches);
+    }
+}
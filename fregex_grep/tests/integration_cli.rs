@@ -0,0 +1,393 @@
+use std::{fs, io::{Read, Write}, path::{Path, PathBuf}, process::{Command, Stdio}};
+
+use flate2::{read::GzEncoder, Compression};
+
+/// Creates a file with the given contents in a new temporary directory of the test.
+fn fixture(test: &str, name: &str, contents: &[u8]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("fregex_grep-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+    return path;
+}
+
+/// Runs the binary with the given arguments and standard input.
+/// Returns its standard output and exit code.
+fn run(args: &[&str], input: &[u8]) -> (String, i32) {
+    return run_in(&std::env::current_dir().unwrap(), args, input);
+}
+
+/// Runs the binary in the given working directory, with the given arguments and standard input.
+/// Returns its standard output and exit code.
+fn run_in(directory: &Path, args: &[&str], input: &[u8]) -> (String, i32) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_fregex_grep"))
+        .current_dir(directory)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // The binary may exit before it reads the whole input, e.g. for the quiet mode.
+    let _ = child.stdin.take().unwrap().write_all(input);
+    let output = child.wait_with_output().unwrap();
+    return (String::from_utf8_lossy(&output.stdout).to_string(), output.status.code().unwrap());
+}
+
+const ANCHOR_INPUTS: &'static [(&[&str], &str, &str)] = &[
+    (&["-c", "-p", "^foo"], "foo\nxfoo\nfoo bar\n", "2\n"),
+    (&["-n", "-p", "foo$"], "foo\nfoo bar\nbar foo\n", "1:foo\n3:bar foo\n"),
+    (&["-v", "-p", "^foo"], "foo\nxfoo\nfoo bar\n", "xfoo\n"),
+    (&["-o", "-b", "-p", "^fo"], "foo\nxfoo\nfoo\n", "0:fo\n9:fo\n"),
+    (&["-c", "-p", "^$"], "a\n\nb\n", "1\n"),
+    (&["-x", "-p", "^foo$"], "foo\nfoo bar\n", "foo\n"),
+    (&["--crlf", "-p", "foo$"], "foo\r\nfoo bar\r\n", "foo\r\n"),
+    (&["--null-data", "-c", "-p", "^b"], "a\0b\0ab\0", "1\n"),
+    (&["--replace", "X", "-p", "^foo"], "foo foo\n", "X foo\n"),
+];
+
+/// Test that the anchors match at the start and end of every line, with both engines.
+#[test]
+fn test_anchors() {
+    for (args, input, expected) in ANCHOR_INPUTS {
+        for engine in [&[][..], &["--original"][..]] {
+            let args: Vec<&str> = args.iter().chain(engine).copied().collect();
+            let (output, _) = run(&args, input.as_bytes());
+            assert_eq!(*expected, output, "Unexpected output with {:?}", args);
+        }
+    }
+}
+
+/// Test that the anchored patterns are verified as the same matches with both engines.
+#[test]
+fn test_verify_anchors() {
+    let path = fixture("verify_anchors", "anchors.txt", b"foo\nxfoo\nbar foo\n");
+    let (output, code) = run(&["--verify", "-p", "^foo", "-p", "foo$", path.to_str().unwrap()], b"");
+
+    assert_eq!("Verified 1 files with 3 matches: 0 mismatches\n", output);
+    assert_eq!(0, code);
+
+    // With CRLF line terminators, a lone carriage return ends a line for the anchors of both.
+    let path = fixture("verify_anchors", "crlf.txt", b"a\rfoo\r\nfoo\rb\r\n");
+    let (output, code) = run(&["--crlf", "-x", "--verify", "-p", "foo", path.to_str().unwrap()], b"");
+
+    assert_eq!("Verified 1 files with 2 matches: 0 mismatches\n", output);
+    assert_eq!(0, code);
+}
+
+/// Test that the invalid UTF-8 bytes don't end the records when NUL bytes end them.
+#[test]
+fn test_invalid_bytes_null_data() {
+    let (output, _) = run(&["--null-data", "-n", "-p", "bar"], b"foo\xffbar\0");
+    assert_eq!("1:foo\u{FFFD}bar\0", output);
+
+    let (output, code) = run(&["--null-data", "-x", "-p", "bar"], b"foo\xffbar\0");
+    assert_eq!(("", 1), (output.as_str(), code));
+}
+
+/// Test that replacing in place through a symbolic link replaces the file it points to.
+#[cfg(unix)]
+#[test]
+fn test_in_place_symlink() {
+    let target = fixture("in_place_symlink", "target.txt", b"foo bar\n");
+    let link = target.with_file_name("link.txt");
+    let _ = fs::remove_file(&link);
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    let (_, code) = run(&["-p", "foo", "--replace", "baz", "--in-place", link.to_str().unwrap()], b"");
+    assert_eq!(0, code);
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!("baz bar\n", fs::read_to_string(&target).unwrap());
+}
+
+const OUTPUT_INPUTS: &'static [(&[&str], &str, &str)] = &[
+    (&["-n", "-p", "foo"], "foo\nbar\nfoo bar\n", "1:foo\n3:foo bar\n"),
+    (&["-b", "-p", "bar"], "foo\nbar\n", "4:bar\n"),
+    (&["-o", "-p", "o+", "-p", "b"], "foo\nboo\n", "oo\nb\noo\n"),
+    (&["--tuples", "-p", "o+"], "foo\nboo\n", "(1, 3)\n(5, 7)\n"),
+    (&["-w", "-p", "foo"], "foobar\nfoo bar\n", "foo bar\n"),
+    (&["-x", "-p", "foo"], "foo\nfoo bar\n", "foo\n"),
+];
+
+/// Test the lines and matches printed for the options that select and format them.
+#[test]
+fn test_output() {
+    for (args, input, expected) in OUTPUT_INPUTS {
+        let (output, _) = run(args, input.as_bytes());
+        assert_eq!(*expected, output, "Unexpected output with {:?}", args);
+    }
+}
+
+/// Test that the exit code tells whether any line was selected, or whether an error occurred.
+#[test]
+fn test_exit_codes() {
+    assert_eq!(("foo\n".to_string(), 0), run(&["-p", "foo"], b"foo\n"));
+    assert_eq!((String::new(), 1), run(&["-p", "bar"], b"foo\n"));
+
+    let missing = std::env::temp_dir().join("fregex_grep-missing").join("missing.txt");
+    assert_eq!(2, run(&["-p", "foo", missing.to_str().unwrap()], b"").1);
+    assert_eq!(2, run(&["-p", "(foo"], b"foo\n").1);
+}
+
+const CONTEXT_INPUTS: &'static [(&[&str], &str, &str)] = &[
+    (&["-C", "1", "-p", "foo"], "a\nfoo\nb\nc\nd\nfoo\ne\n", "a\nfoo\nb\n--\nd\nfoo\ne\n"),
+    (&["-C", "1", "-n", "-p", "foo"], "foo\nb\nfoo\nc\n", "1:foo\n2-b\n3:foo\n4-c\n"),
+    (&["-A", "2", "-p", "foo"], "foo\nfoo\na\nb\nc\n", "foo\nfoo\na\nb\n"),
+    (&["-B", "1", "-p", "foo"], "a\nb\nfoo\n", "b\nfoo\n"),
+    (&["-A", "1", "-b", "-p", "foo"], "foo\nbar\nbaz\n", "0:foo\n4-bar\n"),
+];
+
+/// Test that the context lines are printed around the matching lines, and that
+/// overlapping groups of lines are merged.
+#[test]
+fn test_context() {
+    for (args, input, expected) in CONTEXT_INPUTS {
+        let (output, _) = run(args, input.as_bytes());
+        assert_eq!(*expected, output, "Unexpected output with {:?}", args);
+    }
+}
+
+const MODE_INPUTS: &'static [(&[&str], &str, &str, i32)] = &[
+    (&["-v", "-p", "foo"], "foo\na\nfoo bar\nb\n", "a\nb\n", 0),
+    (&["-v", "-p", "foo"], "foo\n", "", 1),
+    (&["-c", "-p", "foo"], "foo\nfoo\nbar\n", "2\n", 0),
+    (&["-v", "-c", "-p", "foo"], "foo\na\n", "1\n", 0),
+    (&["-c", "-p", "baz"], "foo\n", "0\n", 1),
+    (&["-q", "-p", "foo"], "foo\n", "", 0),
+    (&["-q", "-p", "baz"], "foo\n", "", 1),
+    (&["-l", "-p", "foo"], "foo\n", "(standard input)\n", 0),
+    (&["-L", "-p", "foo"], "foo\n", "", 0),
+    (&["-L", "-p", "baz"], "foo\n", "(standard input)\n", 1),
+];
+
+/// Test the inverted, count, quiet and file listing modes, with their exit codes.
+#[test]
+fn test_modes() {
+    for (args, input, expected, code) in MODE_INPUTS {
+        let output = run(args, input.as_bytes());
+        assert_eq!((expected.to_string(), *code), output, "Unexpected output with {:?}", args);
+    }
+}
+
+/// Test that the lines around the ends of the buffers of a stream are searched and numbered
+/// like the rest, and that the context is kept from one buffer to the next.
+#[test]
+fn test_stream_buffers() {
+    let input: String = (0..20_000).map(|i| format!("line {:05}\n", i)).collect();
+
+    // Each line takes 11 bytes, so the first buffer ends within line 5958.
+    let (output, code) = run(&["-n", "-C", "1", "-p", "line 0595[6-8]$"], input.as_bytes());
+    assert_eq!("5956-line 05955\n5957:line 05956\n5958:line 05957\n5959:line 05958\n5960-line 05959\n", output);
+    assert_eq!(0, code);
+
+    let (output, _) = run(&["-c", "-p", "^line 1"], input.as_bytes());
+    assert_eq!("10000\n", output);
+
+    let (output, _) = run(&["-c", "-v", "-p", "5$"], input.as_bytes());
+    assert_eq!("18000\n", output);
+
+    // The same text is searched at once when it is read from a file.
+    let path = fixture("stream_buffers", "lines.txt", input.as_bytes());
+    let (output, _) = run(&["-b", "-p", "line 05957", path.to_str().unwrap()], b"");
+    assert_eq!("65527:line 05957\n", output);
+}
+
+/// Test that the matches spanning multiple lines are found across the ends of the buffers
+/// of a stream, just like in a file.
+#[test]
+fn test_stream_multi_line() {
+    let input = format!("{}\na\nb\ny\n", "x".repeat(65533));
+    let path = fixture("stream_multi_line", "lines.txt", input.as_bytes());
+
+    for engine in [&[][..], &["--original"][..]] {
+        let args: Vec<&str> = ["-c", "-p", "a\nb"].iter().chain(engine).copied().collect();
+        assert_eq!(("2\n".to_string(), 0), run(&args, input.as_bytes()), "Unexpected stream output with {:?}", args);
+
+        let args: Vec<&str> = args.iter().chain(&[path.to_str().unwrap()]).copied().collect();
+        assert_eq!(("2\n".to_string(), 0), run(&args, b""), "Unexpected file output with {:?}", args);
+    }
+}
+
+/// Test that the JSON records describe the files, the lines and the matches in them.
+#[test]
+fn test_json() {
+    let (output, code) = run(&["--json", "-p", "o+"], b"foo\nbar\n");
+    let expected = concat!(
+        "{\"type\":\"begin\",\"data\":{\"path\":{\"text\":\"(standard input)\"}}}\n",
+        "{\"type\":\"match\",\"data\":{\"path\":{\"text\":\"(standard input)\"},\"lines\":{\"text\":\"foo\"},\"line_number\":1,",
+        "\"absolute_offset\":0,\"submatches\":[{\"match\":{\"text\":\"oo\"},\"pattern\":0,\"start\":1,\"end\":3}]}}\n",
+        "{\"type\":\"end\",\"data\":{\"path\":{\"text\":\"(standard input)\"},\"binary\":false,\"selected\":true,\"stats\":{\"matched_lines\":1,\"matches\":1}}}\n",
+        "{\"type\":\"summary\",\"data\":{\"files\":1,\"files_matched\":1,\"matched_lines\":1,\"matches\":1}}\n",
+    );
+
+    assert_eq!(expected, output);
+    assert_eq!(0, code);
+}
+
+const REPLACE_INPUTS: &'static [(&[&str], &str, &str)] = &[
+    (&["--replace", "<$1>", "-p", "f(o+)"], "foo bar fo\n", "<oo> bar <o>\n"),
+    (&["--replace", "[$0]", "-o", "-p", "a+"], "baa a\n", "[aa]\n[a]\n"),
+    (&["--replace", "1", "--replace", "2", "-p", "a", "-p", "b"], "ab\n", "12\n"),
+];
+
+/// Test that the matches are replaced with the templates in the printed lines.
+#[test]
+fn test_replace() {
+    for (args, input, expected) in REPLACE_INPUTS {
+        let (output, _) = run(args, input.as_bytes());
+        assert_eq!(*expected, output, "Unexpected output with {:?}", args);
+    }
+}
+
+const LIMIT_INPUTS: &'static [(&[&str], &str, &str)] = &[
+    (&["-m", "2", "-p", "foo"], "foo 1\nfoo 2\nfoo 3\n", "foo 1\nfoo 2\n"),
+    (&["-m", "1", "-A", "1", "-p", "foo"], "foo 1\nfoo 2\nfoo 3\n", "foo 1\nfoo 2\n"),
+    (&["-m", "1", "-c", "-p", "foo"], "foo\nfoo\n", "1\n"),
+    (&["-o", "-m", "1", "-p", "o"], "foo\nfoo\n", "o\no\n"),
+    (&["--first", "-o", "-p", "o"], "foo\nfoo\n", "o\n"),
+    (&["--max-columns", "4", "-p", "foo"], "a foo bar\n", "a fo [... omitted end of long line]\n"),
+];
+
+/// Test that the searches stop at the maximum count of lines, and at the first match.
+#[test]
+fn test_limits() {
+    for (args, input, expected) in LIMIT_INPUTS {
+        let (output, _) = run(args, input.as_bytes());
+        assert_eq!(*expected, output, "Unexpected output with {:?}", args);
+    }
+}
+
+/// Test that only the given range of a file is searched, with the offsets in the file.
+#[test]
+fn test_offset() {
+    let path = fixture("offset", "offset.txt", b"foo\nfoo\nfoo\n");
+    let (output, _) = run(&["-b", "-n", "--offset", "4:8", "-p", "foo", path.to_str().unwrap()], b"");
+    assert_eq!("2:4:foo\n", output);
+}
+
+/// Test that NUL bytes end the records instead of the line feeds.
+#[test]
+fn test_null_data() {
+    let (output, _) = run(&["--null-data", "-p", "foo"], b"foo\0bar\0foo\nbar\0");
+    assert_eq!("foo\0foo\nbar\0", output);
+}
+
+/// Test that several patterns with anchors are searched for in NUL-terminated records.
+#[test]
+fn test_null_data_patterns() {
+    for engine in [&[][..], &["--original"][..]] {
+        let args: Vec<&str> = ["--null-data", "-o", "-p", ".$", "-p", "^[ab]"].iter().chain(engine).copied().collect();
+        let (output, code) = run(&args, b"a\nb\0xa\0");
+        assert_eq!(("a\0b\0a\0", 0), (output.as_str(), code), "Unexpected output with {:?}", args);
+    }
+}
+
+/// Creates a directory tree with ignored, hidden and linked files in a new temporary directory
+/// of the test. Every file of the tree contains the same matching line.
+fn tree(test: &str) -> PathBuf {
+    let root = fixture(test, "a.txt", b"foo\n").parent().unwrap().to_path_buf();
+    for name in ["b.rs", "ignored.txt", ".hidden.txt", "sub/c.txt"] {
+        fs::create_dir_all(root.join(name).parent().unwrap()).unwrap();
+        fs::write(root.join(name), b"foo\n").unwrap();
+    }
+    fs::write(root.join(".gitignore"), b"ignored.txt\n").unwrap();
+
+    std::os::unix::fs::symlink("a.txt", root.join("link.txt")).unwrap();
+    std::os::unix::fs::symlink("sub", root.join("linked")).unwrap();
+    return root;
+}
+
+const WALK_INPUTS: &'static [(&[&str], &[&str])] = &[
+    (&["-r"], &["a.txt", "b.rs", "sub/c.txt"]),
+    (&["-R"], &["a.txt", "b.rs", "link.txt", "linked/c.txt", "sub/c.txt"]),
+    (&["-r", "--include", "*.txt"], &["a.txt", "sub/c.txt"]),
+    (&["-r", "--include", "*.txt", "--include", "*.rs"], &["a.txt", "b.rs", "sub/c.txt"]),
+    (&["-r", "--exclude", "*.txt"], &["b.rs"]),
+    (&["-r", "--exclude", "sub"], &["a.txt", "b.rs"]),
+    (&["-r", "--include", "*.txt", "--exclude", "c.*"], &["a.txt"]),
+    (&["-r", "--no-ignore"], &["a.txt", "b.rs", "ignored.txt", "sub/c.txt"]),
+    (&["-r", "--hidden"], &[".hidden.txt", "a.txt", "b.rs", "sub/c.txt"]),
+    (&["-r", "--hidden", "--include", "*.txt"], &[".hidden.txt", "a.txt", "sub/c.txt"]),
+    (&["-R", "--include", "c.txt"], &["linked/c.txt", "sub/c.txt"]),
+];
+
+/// Test that the recursive search only lists the files that are selected by the include
+/// and exclude globs, and are not ignored, hidden or linked (unless requested).
+#[test]
+fn test_walk() {
+    let root = tree("walk");
+
+    for (args, expected) in WALK_INPUTS {
+        let args: Vec<&str> = args.iter().chain(&["-l", "-p", "foo"]).copied().collect();
+        let (output, code) = run_in(&root, &args, b"");
+
+        // The order of the files in a directory is not specified.
+        let mut files: Vec<&str> = output.lines().collect();
+        files.sort();
+        assert_eq!((expected.to_vec(), 0), (files, code), "Unexpected files with {:?}", args);
+    }
+}
+
+/// Test that directories are only searched with recursion, and that the given paths
+/// are walked with their prefix.
+#[test]
+fn test_walk_paths() {
+    let root = tree("walk_paths");
+
+    let (output, code) = run_in(&root, &["-p", "foo", "sub"], b"");
+    assert_eq!(("", 2), (output.as_str(), code));
+
+    let (output, code) = run_in(&root, &["-r", "-l", "-p", "foo", "sub", "a.txt"], b"");
+    assert_eq!(("sub/c.txt\na.txt\n", 0), (output.as_str(), code));
+
+    let (output, code) = run_in(&root, &["-r", "-l", "-p", "foo", "linked"], b"");
+    assert_eq!(("linked/c.txt\n", 0), (output.as_str(), code));
+}
+
+/// Compresses the given bytes with gzip.
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut compressed = vec![];
+    GzEncoder::new(bytes, Compression::default()).read_to_end(&mut compressed).unwrap();
+    return compressed;
+}
+
+/// Test that the compressed files are searched in their decompressed contents, which
+/// the line numbers and the offsets are counted in.
+#[test]
+fn test_search_zip() {
+    let compressed = gzip(b"foo\nbar\nbaz bar\n");
+    let path = fixture("search_zip", "lines.txt.gz", &compressed);
+
+    let (output, code) = run(&["-z", "-n", "-b", "-p", "bar"], &compressed);
+    assert_eq!(("2:4:bar\n3:8:baz bar\n", 0), (output.as_str(), code));
+
+    let (output, code) = run(&["-z", "-o", "-b", "-p", "ba.", path.to_str().unwrap()], b"");
+    assert_eq!(("4:bar\n8:baz\n12:bar\n", 0), (output.as_str(), code));
+
+    let (output, code) = run(&["-z", "-c", "-p", "qux"], &compressed);
+    assert_eq!(("0\n", 1), (output.as_str(), code));
+
+    // Files that aren't compressed are searched as they are.
+    let (output, code) = run(&["-z", "-n", "-p", "bar"], b"foo\nbar\n");
+    assert_eq!(("2:bar\n", 0), (output.as_str(), code));
+
+    // Without decompression, the compressed bytes themselves are searched.
+    let (output, code) = run(&["-c", "-p", "bar"], &compressed);
+    assert_eq!(("0\n", 1), (output.as_str(), code));
+}
+
+/// Test that the corrupt compressed files are reported as errors.
+#[test]
+fn test_search_zip_corrupt() {
+    let mut compressed = gzip(b"foo\nbar\n");
+    compressed.truncate(compressed.len() / 2);
+    let path = fixture("search_zip_corrupt", "lines.txt.gz", &compressed);
+
+    let (_, code) = run(&["-z", "-p", "foo"], &compressed);
+    assert_eq!(2, code);
+
+    let (_, code) = run(&["-z", "-p", "foo", path.to_str().unwrap()], b"");
+    assert_eq!(2, code);
+}